@@ -1,3 +1,6 @@
+use std::convert::TryFrom;
+
+use state_space::algorithm::{Algorithm, UninformedAlgorithm};
 use state_space::StateSpace;
 
 fn main() {
@@ -23,6 +26,20 @@ fn main() {
         goal,
         p.depth_first_search(initial, goal)
     );
+
+    // Algorithms selected at runtime are checked against what the state space
+    // provides.
+    for name in ["breadth_first_search", "a_star"] {
+        let algorithm: Algorithm = name.parse().expect("Algorithm should exist");
+        match UninformedAlgorithm::try_from(algorithm) {
+            Ok(algorithm) => println!(
+                "\nSteps found using {}: {:?}",
+                name,
+                algorithm.run(&p, initial, goal).path
+            ),
+            Err(error) => println!("\n{}", error),
+        }
+    }
 }
 
 pub struct KnightMove {
@@ -42,8 +59,8 @@ impl KnightMove {
 impl StateSpace for KnightMove {
     type State = (isize, isize);
 
-    fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
-        let offsets = vec![
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        const OFFSETS: [(isize, isize); 8] = [
             (-1, -2),
             (1, -2),
             (2, -1),
@@ -54,9 +71,9 @@ impl StateSpace for KnightMove {
             (-2, -1),
         ];
 
-        offsets
-            .into_iter()
-            .map(|(i, j)| (state.0.checked_add(i), state.1.checked_add(j)))
+        OFFSETS
+            .iter()
+            .map(|&(i, j)| (state.0.checked_add(i), state.1.checked_add(j)))
             .filter_map(|(i, j)| {
                 if let (Some(i), Some(j)) = (i, j) {
                     Some((i, j))
@@ -65,7 +82,7 @@ impl StateSpace for KnightMove {
                 }
             })
             .filter(|s| self.is_in_board(*s))
-            .collect()
+            .for_each(f);
     }
 }
 
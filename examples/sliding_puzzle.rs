@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use state_space::ida_star::ida_star;
+use state_space::sliding_puzzle::{PatternDatabases, PuzzleHeuristic, SlidingPuzzle};
+use state_space::HeuristicStateSpace;
+
+fn main() {
+    let puzzle = SlidingPuzzle::new(3, 3);
+    let mut rng = StdRng::seed_from_u64(0);
+    let boards: Vec<_> = (0..20).map(|_| puzzle.random_board(&mut rng)).collect();
+
+    let databases = PatternDatabases::new(&puzzle, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+    let heuristics = [
+        ("Manhattan", PuzzleHeuristic::Manhattan),
+        ("Linear conflict", PuzzleHeuristic::LinearConflict),
+        (
+            "Pattern databases",
+            PuzzleHeuristic::PatternDatabases(Arc::new(databases)),
+        ),
+    ];
+
+    for (name, heuristic) in &heuristics {
+        let puzzle = puzzle.clone().with_heuristic(heuristic.clone());
+
+        let start = Instant::now();
+        let moves: usize = boards
+            .iter()
+            .map(|board| puzzle.a_star(board.clone(), puzzle.goal()).len() - 1)
+            .sum();
+        let a_star = start.elapsed();
+
+        let start = Instant::now();
+        let expanded: usize = boards
+            .iter()
+            .map(|board| {
+                ida_star(&puzzle, board.clone(), &puzzle.goal())
+                    .stats
+                    .expanded
+            })
+            .sum();
+        let ida = start.elapsed();
+
+        println!(
+            "{name}: {moves} moves, A* in {a_star:?}, IDA* in {ida:?} expanding {expanded} states"
+        );
+    }
+}
@@ -0,0 +1,634 @@
+//! The search algorithms which can be selected with a
+//! [`SearchBuilder`](crate::search::SearchBuilder).
+//!
+//! Each algorithm is a unit type implementing [`SearchAlgorithm`] only for
+//! the state spaces it can run on, so selecting [`AStar`] on a space without
+//! a heuristic is rejected at compile time.
+//!
+//! An [`Algorithm`] selected at runtime runs on a [`HeuristicStateSpace`],
+//! and is checked to run on lesser state spaces by converting it into an
+//! [`UninformedAlgorithm`] or a [`CostAlgorithm`].
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::search::SearchResult;
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// The order in which the open states are expanded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrontierKind {
+    /// First generated states are expanded first.
+    Fifo,
+    /// Last generated states are expanded first.
+    Lifo,
+    /// Open states are expanded in a random order.
+    Random,
+    /// States with the lowest priority are expanded first.
+    Priority,
+}
+
+/// An algorithm which can be run on the state space `S`.
+pub trait SearchAlgorithm<S: StateSpace + ?Sized> {
+    /// Returns the order in which the open states are expanded.
+    fn frontier(&self) -> FrontierKind;
+
+    /// Returns the cost of the transition from `current` to `next` as seen by
+    /// the algorithm.
+    #[inline]
+    fn cost(&self, _space: &S, _current: &S::State, _next: &S::State) -> f32 {
+        1.
+    }
+
+    /// Returns the priority of `state` reached with cost `cost`, only used
+    /// with [`FrontierKind::Priority`].
+    #[inline]
+    fn priority(&self, _space: &S, _state: &S::State, _goal: &S::State, cost: f32) -> f32 {
+        cost
+    }
+
+    /// Returns whether a state already reached is reopened when a cheaper
+    /// path to it is found.
+    #[inline]
+    fn reopens(&self) -> bool {
+        false
+    }
+}
+
+/// See [`StateSpace::random_search`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RandomSearch;
+
+impl<S: StateSpace + ?Sized> SearchAlgorithm<S> for RandomSearch {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Random
+    }
+}
+
+/// See [`StateSpace::breadth_first_search`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BreadthFirstSearch;
+
+impl<S: StateSpace + ?Sized> SearchAlgorithm<S> for BreadthFirstSearch {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Fifo
+    }
+}
+
+/// See [`StateSpace::depth_first_search`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DepthFirstSearch;
+
+impl<S: StateSpace + ?Sized> SearchAlgorithm<S> for DepthFirstSearch {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Lifo
+    }
+}
+
+/// See [`CostStateSpace::dijkstra`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Dijkstra;
+
+impl<S: CostStateSpace + ?Sized> SearchAlgorithm<S> for Dijkstra {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Priority
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        space.cost(current, next)
+    }
+
+    #[inline]
+    fn reopens(&self) -> bool {
+        true
+    }
+}
+
+/// See [`HeuristicStateSpace::greedy_search`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GreedySearch;
+
+impl<S: HeuristicStateSpace + ?Sized> SearchAlgorithm<S> for GreedySearch {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Priority
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        space.cost(current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, _cost: f32) -> f32 {
+        space.heuristic(state, goal)
+    }
+}
+
+/// See [`HeuristicStateSpace::a_star`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AStar;
+
+impl<S: HeuristicStateSpace + ?Sized> SearchAlgorithm<S> for AStar {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Priority
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        space.cost(current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, cost: f32) -> f32 {
+        cost + space.heuristic(state, goal)
+    }
+
+    #[inline]
+    fn reopens(&self) -> bool {
+        true
+    }
+}
+
+/// A* with the heuristic scaled by a weight, trading optimality for speed.
+///
+/// With a weight `w` of at least 1 and an *optimistic* heuristic, the
+/// solution found costs at most `w` times the optimal one. States are not
+/// reopened, which keeps the bound for consistent heuristics.
+///
+/// # Properties
+/// - Complete: Yes
+/// - Optimal: No (unless the weight is 1)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WeightedAStar(pub f32);
+
+impl<S: HeuristicStateSpace + ?Sized> SearchAlgorithm<S> for WeightedAStar {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        FrontierKind::Priority
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        space.cost(current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, cost: f32) -> f32 {
+        self.0.mul_add(space.heuristic(state, goal), cost)
+    }
+}
+
+/// An algorithm selected at runtime, for instance from a configuration file.
+///
+/// It parses from and displays as the name of the corresponding method, e.g.
+/// `"a_star"` for [`HeuristicStateSpace::a_star`].
+///
+/// ```
+/// # use state_space::algorithm::Algorithm;
+/// let algorithm: Algorithm = "breadth_first_search".parse().unwrap();
+///
+/// assert_eq!(algorithm, Algorithm::BreadthFirstSearch);
+/// assert!(algorithm.is_optimal());
+/// assert!(!algorithm.needs_heuristic());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// See [`RandomSearch`].
+    RandomSearch,
+    /// See [`BreadthFirstSearch`].
+    BreadthFirstSearch,
+    /// See [`DepthFirstSearch`].
+    DepthFirstSearch,
+    /// See [`Dijkstra`].
+    Dijkstra,
+    /// See [`GreedySearch`].
+    GreedySearch,
+    /// See [`AStar`].
+    AStar,
+}
+
+impl Algorithm {
+    /// Every algorithm, from the least to the most demanding on the state
+    /// space.
+    pub const ALL: [Self; 6] = [
+        Self::RandomSearch,
+        Self::BreadthFirstSearch,
+        Self::DepthFirstSearch,
+        Self::Dijkstra,
+        Self::GreedySearch,
+        Self::AStar,
+    ];
+
+    /// Returns the name of the algorithm.
+    #[inline]
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::RandomSearch => "random_search",
+            Self::BreadthFirstSearch => "breadth_first_search",
+            Self::DepthFirstSearch => "depth_first_search",
+            Self::Dijkstra => "dijkstra",
+            Self::GreedySearch => "greedy_search",
+            Self::AStar => "a_star",
+        }
+    }
+
+    /// Returns whether the algorithm finds a solution whenever one exists in a
+    /// finite state space.
+    #[inline]
+    #[must_use]
+    pub const fn is_complete(self) -> bool {
+        matches!(
+            self,
+            Self::BreadthFirstSearch | Self::Dijkstra | Self::AStar
+        )
+    }
+
+    /// Returns whether the solution found is the least costly one, assuming an
+    /// *optimistic* heuristic for [`Algorithm::AStar`] and unit costs for
+    /// [`Algorithm::BreadthFirstSearch`].
+    #[inline]
+    #[must_use]
+    pub const fn is_optimal(self) -> bool {
+        matches!(
+            self,
+            Self::BreadthFirstSearch | Self::Dijkstra | Self::AStar
+        )
+    }
+
+    /// Returns whether the algorithm requires a [`CostStateSpace`].
+    #[inline]
+    #[must_use]
+    pub const fn needs_cost(self) -> bool {
+        matches!(self, Self::Dijkstra | Self::GreedySearch | Self::AStar)
+    }
+
+    /// Returns whether the algorithm requires a [`HeuristicStateSpace`].
+    #[inline]
+    #[must_use]
+    pub const fn needs_heuristic(self) -> bool {
+        matches!(self, Self::GreedySearch | Self::AStar)
+    }
+
+    /// Runs the algorithm on `space` from `init` to `goal`.
+    ///
+    /// Use [`SearchBuilder::algorithm`](crate::search::SearchBuilder::algorithm)
+    /// to configure the search further.
+    pub fn run<S: HeuristicStateSpace + ?Sized>(
+        self,
+        space: &S,
+        init: S::State,
+        goal: S::State,
+    ) -> SearchResult<S::State> {
+        space.search().algorithm(self).run(init, goal)
+    }
+}
+
+impl fmt::Display for Algorithm {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = ParseAlgorithmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| ParseAlgorithmError(s.to_owned()))
+    }
+}
+
+impl Algorithm {
+    /// Returns the algorithm if it runs on any state space.
+    fn uninformed<'a, S: StateSpace + ?Sized + 'a>(self) -> Option<&'a dyn SearchAlgorithm<S>> {
+        match self {
+            Self::RandomSearch => Some(&RandomSearch),
+            Self::BreadthFirstSearch => Some(&BreadthFirstSearch),
+            Self::DepthFirstSearch => Some(&DepthFirstSearch),
+            Self::Dijkstra | Self::GreedySearch | Self::AStar => None,
+        }
+    }
+
+    /// Returns the algorithm if it runs on any state space with costs.
+    fn with_cost<'a, S: CostStateSpace + ?Sized + 'a>(self) -> Option<&'a dyn SearchAlgorithm<S>> {
+        match self {
+            Self::Dijkstra => Some(&Dijkstra),
+            _ => self.uninformed(),
+        }
+    }
+
+    /// Returns the algorithm, which runs on any state space with a heuristic.
+    fn with_heuristic<'a, S: HeuristicStateSpace + ?Sized + 'a>(
+        self,
+    ) -> &'a dyn SearchAlgorithm<S> {
+        match self {
+            Self::GreedySearch => &GreedySearch,
+            Self::AStar => &AStar,
+            _ => self
+                .with_cost()
+                .expect("Every algorithm should run with a heuristic"),
+        }
+    }
+}
+
+impl<S: HeuristicStateSpace + ?Sized> SearchAlgorithm<S> for Algorithm {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        self.with_heuristic::<S>().frontier()
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        self.with_heuristic().cost(space, current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, cost: f32) -> f32 {
+        self.with_heuristic().priority(space, state, goal, cost)
+    }
+
+    #[inline]
+    fn reopens(&self) -> bool {
+        self.with_heuristic::<S>().reopens()
+    }
+}
+
+/// An [`Algorithm`] checked to run on any [`StateSpace`].
+///
+/// ```
+/// # use std::convert::TryFrom;
+/// # use state_space::StateSpace;
+/// # use state_space::algorithm::{Algorithm, UninformedAlgorithm};
+/// # struct Line;
+/// # impl StateSpace for Line {
+/// #     type State = i32;
+/// #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+/// # }
+/// let algorithm: Algorithm = "breadth_first_search".parse().unwrap();
+/// let algorithm = UninformedAlgorithm::try_from(algorithm).unwrap();
+/// assert_eq!(algorithm.run(&Line, 0, 3).cost, Some(3.));
+///
+/// assert!(UninformedAlgorithm::try_from(Algorithm::AStar).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UninformedAlgorithm(Algorithm);
+
+impl UninformedAlgorithm {
+    /// Returns the algorithm.
+    #[inline]
+    #[must_use]
+    pub const fn algorithm(self) -> Algorithm {
+        self.0
+    }
+
+    /// Runs the algorithm on `space` from `init` to `goal`.
+    pub fn run<S: StateSpace + ?Sized>(
+        self,
+        space: &S,
+        init: S::State,
+        goal: S::State,
+    ) -> SearchResult<S::State> {
+        space.search().algorithm(self).run(init, goal)
+    }
+
+    fn inner<'a, S: StateSpace + ?Sized + 'a>(self) -> &'a dyn SearchAlgorithm<S> {
+        self.0
+            .uninformed()
+            .expect("Algorithm should have been checked not to need costs")
+    }
+}
+
+impl TryFrom<Algorithm> for UninformedAlgorithm {
+    type Error = UnsupportedAlgorithm;
+
+    #[inline]
+    fn try_from(algorithm: Algorithm) -> Result<Self, Self::Error> {
+        if algorithm.needs_cost() {
+            Err(UnsupportedAlgorithm(algorithm))
+        } else {
+            Ok(Self(algorithm))
+        }
+    }
+}
+
+impl<S: StateSpace + ?Sized> SearchAlgorithm<S> for UninformedAlgorithm {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        self.inner::<S>().frontier()
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        self.inner().cost(space, current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, cost: f32) -> f32 {
+        self.inner().priority(space, state, goal, cost)
+    }
+
+    #[inline]
+    fn reopens(&self) -> bool {
+        self.inner::<S>().reopens()
+    }
+}
+
+/// An [`Algorithm`] checked to run on any [`CostStateSpace`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CostAlgorithm(Algorithm);
+
+impl CostAlgorithm {
+    /// Returns the algorithm.
+    #[inline]
+    #[must_use]
+    pub const fn algorithm(self) -> Algorithm {
+        self.0
+    }
+
+    /// Runs the algorithm on `space` from `init` to `goal`.
+    pub fn run<S: CostStateSpace + ?Sized>(
+        self,
+        space: &S,
+        init: S::State,
+        goal: S::State,
+    ) -> SearchResult<S::State> {
+        space.search().algorithm(self).run(init, goal)
+    }
+
+    fn inner<'a, S: CostStateSpace + ?Sized + 'a>(self) -> &'a dyn SearchAlgorithm<S> {
+        self.0
+            .with_cost()
+            .expect("Algorithm should have been checked not to need a heuristic")
+    }
+}
+
+impl TryFrom<Algorithm> for CostAlgorithm {
+    type Error = UnsupportedAlgorithm;
+
+    #[inline]
+    fn try_from(algorithm: Algorithm) -> Result<Self, Self::Error> {
+        if algorithm.needs_heuristic() {
+            Err(UnsupportedAlgorithm(algorithm))
+        } else {
+            Ok(Self(algorithm))
+        }
+    }
+}
+
+impl<S: CostStateSpace + ?Sized> SearchAlgorithm<S> for CostAlgorithm {
+    #[inline]
+    fn frontier(&self) -> FrontierKind {
+        self.inner::<S>().frontier()
+    }
+
+    #[inline]
+    fn cost(&self, space: &S, current: &S::State, next: &S::State) -> f32 {
+        self.inner().cost(space, current, next)
+    }
+
+    #[inline]
+    fn priority(&self, space: &S, state: &S::State, goal: &S::State, cost: f32) -> f32 {
+        self.inner().priority(space, state, goal, cost)
+    }
+
+    #[inline]
+    fn reopens(&self) -> bool {
+        self.inner::<S>().reopens()
+    }
+}
+
+/// The error returned when converting an [`Algorithm`] needing more than the
+/// state space provides.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnsupportedAlgorithm(pub Algorithm);
+
+impl fmt::Display for UnsupportedAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let needs = if self.0.needs_heuristic() {
+            "a heuristic"
+        } else {
+            "costs"
+        };
+        write!(f, "search algorithm `{}` needs {}", self.0, needs)
+    }
+}
+
+impl Error for UnsupportedAlgorithm {}
+
+/// The error returned when parsing an unknown [`Algorithm`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAlgorithmError(String);
+
+impl fmt::Display for ParseAlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown search algorithm `{}`", self.0)
+    }
+}
+
+impl Error for ParseAlgorithmError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+
+    #[test]
+    fn name_round_trip() {
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+    }
+
+    #[test]
+    fn parse_unknown() {
+        assert_eq!(
+            "bogo_search".parse::<Algorithm>(),
+            Err(ParseAlgorithmError("bogo_search".to_owned()))
+        );
+    }
+
+    #[test]
+    fn optimal_algorithms_agree() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+
+        for algorithm in Algorithm::ALL {
+            let result = algorithm.run(&grid, (0, 0), (3, 3));
+
+            assert!(result.is_found(), "{} failed", algorithm);
+            if algorithm.is_optimal() {
+                assert_eq!(result.cost, Some(6.), "{algorithm} is not optimal");
+            }
+        }
+    }
+
+    #[test]
+    fn dispatch_per_trait_level() {
+        struct Line;
+
+        impl StateSpace for Line {
+            type State = i32;
+
+            fn neighbours(&self, state: &i32) -> Vec<i32> {
+                vec![state - 1, state + 1]
+            }
+        }
+
+        impl CostStateSpace for Line {
+            fn cost(&self, _current: &i32, next: &i32) -> f32 {
+                if *next > 0 {
+                    2.
+                } else {
+                    1.
+                }
+            }
+        }
+
+        for algorithm in Algorithm::ALL {
+            match UninformedAlgorithm::try_from(algorithm) {
+                Ok(uninformed) if algorithm.is_complete() => {
+                    assert_eq!(uninformed.run(&Line, 0, 2).cost, Some(2.));
+                }
+                Ok(_) => assert!(!algorithm.needs_cost()),
+                Err(error) => assert!(algorithm.needs_cost(), "{}", error),
+            }
+
+            match CostAlgorithm::try_from(algorithm) {
+                Ok(cost) if algorithm == Algorithm::Dijkstra => {
+                    assert_eq!(cost.run(&Line, 0, 2).cost, Some(4.));
+                }
+                Ok(_) => assert!(!algorithm.needs_heuristic()),
+                Err(error) => assert!(algorithm.needs_heuristic(), "{}", error),
+            }
+        }
+
+        assert_eq!(
+            UnsupportedAlgorithm(Algorithm::Dijkstra).to_string(),
+            "search algorithm `dijkstra` needs costs"
+        );
+    }
+
+    #[test]
+    fn weighted_a_star_is_bounded() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+        let result = grid
+            .search()
+            .algorithm(WeightedAStar(1.5))
+            .run((0, 0), (3, 3));
+
+        assert!(result.cost.unwrap() <= 1.5 * 6.);
+    }
+}
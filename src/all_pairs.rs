@@ -0,0 +1,411 @@
+//! Distances between every pair of states of a small finite state space.
+//!
+//! [`AllPairs`] enumerates the states reachable from given roots, then
+//! computes the distances between all of them with the Floyd-Warshall
+//! algorithm or Johnson's algorithm, along with the next state on a shortest
+//! path from each state to each other. Paths are then retrieved in time
+//! proportional to their length.
+//!
+//! The tables take space quadratic in the number of states.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # use state_space::all_pairs::AllPairs;
+//! # struct Ring;
+//! # impl StateSpace for Ring {
+//! #     type State = u8;
+//! #     fn neighbours(&self, state: &u8) -> Vec<u8> { vec![(state + 1) % 6, (state + 5) % 6] }
+//! # }
+//! # impl CostStateSpace for Ring {
+//! #     fn cost(&self, _: &u8, _: &u8) -> f32 { 1. }
+//! # }
+//! let all_pairs = AllPairs::floyd_warshall(&Ring, [0]).unwrap();
+//! let (from, to) = (all_pairs.id(&1).unwrap(), all_pairs.id(&5).unwrap());
+//!
+//! assert_eq!(all_pairs.len(), 6);
+//! assert_eq!(all_pairs.distance(from, to), Some(2.));
+//! assert_eq!(all_pairs.path_between(&1, &5), Some(vec![1, 0, 5]));
+//! ```
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hash};
+
+use crate::arena::{StateArena, StateId};
+use crate::bellman_ford::{bellman_ford, NegativeCycle};
+use crate::priority_queue::MinPrioriyQueue;
+use crate::CostStateSpace;
+
+/// The distances and next states on shortest paths between every pair of
+/// states reachable from some roots, indexed by their [`StateId`].
+#[derive(Clone)]
+pub struct AllPairs<T, H = RandomState> {
+    arena: StateArena<T, H>,
+    /// The distances from each state to each other, row by row, infinite
+    /// between unconnected states.
+    distances: Vec<f32>,
+    /// The next states on shortest paths, row by row.
+    next: Vec<Option<StateId>>,
+}
+
+impl<T: Clone + Eq + Hash> AllPairs<T> {
+    /// Computes the distances between the states reachable from `roots` with
+    /// the Floyd-Warshall algorithm, in time cubic in the number of states.
+    ///
+    /// # Errors
+    ///
+    /// Returns a cycle of negative cost if one is reachable from the roots.
+    pub fn floyd_warshall<S>(
+        space: &S,
+        roots: impl IntoIterator<Item = T>,
+    ) -> Result<Self, NegativeCycle<T>>
+    where
+        S: CostStateSpace<State = T> + ?Sized,
+    {
+        let (arena, edges) = enumerate(space, roots);
+        let n = arena.len();
+
+        let mut distances = vec![f32::INFINITY; n * n];
+        let mut next = vec![None; n * n];
+        for (from, successors) in edges.iter().enumerate() {
+            distances[from * n + from] = 0.;
+            next[from * n + from] = Some(StateId::new(from));
+            for &(to, cost) in successors {
+                let cell = from * n + to.index();
+                if cost < distances[cell] {
+                    distances[cell] = cost;
+                    next[cell] = Some(to);
+                }
+            }
+        }
+
+        for k in 0..n {
+            for i in 0..n {
+                let through = distances[i * n + k];
+                if through.is_infinite() {
+                    continue;
+                }
+                for j in 0..n {
+                    let cost = through + distances[k * n + j];
+                    if cost < distances[i * n + j] {
+                        distances[i * n + j] = cost;
+                        next[i * n + j] = next[i * n + k];
+                    }
+                }
+            }
+        }
+
+        if (0..n).any(|i| distances[i * n + i] < 0.) {
+            return Err(negative_cycle(space, &arena));
+        }
+
+        Ok(Self {
+            arena,
+            distances,
+            next,
+        })
+    }
+
+    /// Computes the distances between the states reachable from `roots` with
+    /// Johnson's algorithm, running a Dijkstra search from each state after
+    /// reweighting the transitions to make their costs non-negative, which is
+    /// faster than [`AllPairs::floyd_warshall`] when states have few
+    /// successors.
+    ///
+    /// # Errors
+    ///
+    /// Returns a cycle of negative cost if one is reachable from the roots.
+    pub fn johnson<S>(
+        space: &S,
+        roots: impl IntoIterator<Item = T>,
+    ) -> Result<Self, NegativeCycle<T>>
+    where
+        S: CostStateSpace<State = T> + ?Sized,
+    {
+        let (arena, edges) = enumerate(space, roots);
+        let n = arena.len();
+
+        // Potentials given by a Bellman-Ford search from a virtual state
+        // leading to every state at no cost.
+        let mut potentials = vec![0_f32; n];
+        for round in 0..=n {
+            let mut improved = false;
+            for (from, successors) in edges.iter().enumerate() {
+                for &(to, cost) in successors {
+                    let potential = potentials[from] + cost;
+                    if potential < potentials[to.index()] {
+                        potentials[to.index()] = potential;
+                        improved = true;
+                    }
+                }
+            }
+
+            if !improved {
+                break;
+            }
+            if round == n {
+                return Err(negative_cycle(space, &arena));
+            }
+        }
+
+        let mut distances = vec![f32::INFINITY; n * n];
+        let mut next = vec![None; n * n];
+        for source in 0..n {
+            let row = &mut distances[source * n..(source + 1) * n];
+            let hops = &mut next[source * n..(source + 1) * n];
+            row[source] = 0.;
+            hops[source] = Some(StateId::new(source));
+
+            let mut settled = vec![false; n];
+            let mut open = MinPrioriyQueue::new();
+            open.enqueue(0., source);
+            while let Some(from) = open.dequeue() {
+                if settled[from] {
+                    continue;
+                }
+                settled[from] = true;
+
+                for &(to, cost) in &edges[from] {
+                    let to = to.index();
+                    // Rounding may make reweighted costs slightly negative.
+                    let reweighted = (cost + potentials[from] - potentials[to]).max(0.);
+                    let distance = row[from] + reweighted;
+                    if distance < row[to] {
+                        row[to] = distance;
+                        hops[to] = if from == source {
+                            Some(StateId::new(to))
+                        } else {
+                            hops[from]
+                        };
+                        open.enqueue(distance, to);
+                    }
+                }
+            }
+
+            for (to, distance) in row.iter_mut().enumerate() {
+                *distance += potentials[to] - potentials[source];
+            }
+        }
+
+        Ok(Self {
+            arena,
+            distances,
+            next,
+        })
+    }
+}
+
+impl<T, H> AllPairs<T, H> {
+    /// Returns the number of states.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns whether there is no state, when no root was given.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+
+    /// Returns the states, indexed by their [`StateId`].
+    #[inline]
+    #[must_use]
+    pub const fn arena(&self) -> &StateArena<T, H> {
+        &self.arena
+    }
+
+    /// Returns the distance from `from` to `to`, if `to` is reachable from
+    /// `from`.
+    #[must_use]
+    pub fn distance(&self, from: StateId, to: StateId) -> Option<f32> {
+        let distance = self.distances[self.cell(from, to)];
+        (distance < f32::INFINITY).then_some(distance)
+    }
+
+    /// Returns the state following `from` on a shortest path to `to`, if `to`
+    /// is reachable from `from`, `from` itself if they are equal.
+    #[inline]
+    #[must_use]
+    pub fn next_hop(&self, from: StateId, to: StateId) -> Option<StateId> {
+        self.next[self.cell(from, to)]
+    }
+
+    /// Returns the states of a shortest path from `from` to `to`, if `to` is
+    /// reachable from `from`.
+    #[must_use]
+    pub fn path(&self, from: StateId, to: StateId) -> Option<Vec<StateId>> {
+        let mut path = vec![from];
+
+        let mut current = from;
+        while current != to {
+            current = self.next_hop(current, to)?;
+            path.push(current);
+        }
+
+        Some(path)
+    }
+
+    fn cell(&self, from: StateId, to: StateId) -> usize {
+        from.index() * self.arena.len() + to.index()
+    }
+}
+
+impl<T: Clone + Eq + Hash, H: BuildHasher> AllPairs<T, H> {
+    /// Returns the identifier of `state`, if it has been enumerated.
+    #[inline]
+    #[must_use]
+    pub fn id(&self, state: &T) -> Option<StateId> {
+        self.arena.get(state)
+    }
+
+    /// Returns the distance from `from` to `to`, if both have been enumerated
+    /// and `to` is reachable from `from`.
+    #[must_use]
+    pub fn distance_between(&self, from: &T, to: &T) -> Option<f32> {
+        self.distance(self.id(from)?, self.id(to)?)
+    }
+
+    /// Returns a shortest path from `from` to `to`, if both have been
+    /// enumerated and `to` is reachable from `from`.
+    #[must_use]
+    pub fn path_between(&self, from: &T, to: &T) -> Option<Vec<T>> {
+        let path = self.path(self.id(from)?, self.id(to)?)?;
+        Some(path.into_iter().map(|id| self.arena[id].clone()).collect())
+    }
+}
+
+/// The transitions from each state along with their costs.
+type Edges = Vec<Vec<(StateId, f32)>>;
+
+/// Returns the states reachable from `roots` along with their transitions.
+fn enumerate<S>(
+    space: &S,
+    roots: impl IntoIterator<Item = S::State>,
+) -> (StateArena<S::State>, Edges)
+where
+    S: CostStateSpace + ?Sized,
+{
+    let mut arena = StateArena::new();
+    let mut queue: VecDeque<_> = roots
+        .into_iter()
+        .filter_map(|root| {
+            let (id, inserted) = arena.intern(root);
+            inserted.then_some(id)
+        })
+        .collect();
+
+    let mut edges = vec![Vec::new(); arena.len()];
+    while let Some(id) = queue.pop_front() {
+        let current = arena[id].clone();
+        space.for_each_neighbour(&current, &mut |neighbour| {
+            let cost = space.cost(&current, &neighbour);
+            let (next, inserted) = arena.intern(neighbour);
+            if inserted {
+                edges.push(Vec::new());
+                queue.push_back(next);
+            }
+            edges[id.index()].push((next, cost));
+        });
+    }
+
+    (arena, edges)
+}
+
+/// Returns a cycle of negative cost among the states of `arena`, which are
+/// all reachable from the roots interned first.
+fn negative_cycle<S>(space: &S, arena: &StateArena<S::State>) -> NegativeCycle<S::State>
+where
+    S: CostStateSpace + ?Sized,
+{
+    arena
+        .states()
+        .iter()
+        .find_map(|state| bellman_ford(space, state.clone()).err())
+        .expect("A negative cycle should be reachable from the roots")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+    use crate::StateSpace;
+
+    #[test]
+    fn agree_with_dijkstra() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+        let floyd_warshall = AllPairs::floyd_warshall(&grid, [(0, 0)]).unwrap();
+        let johnson = AllPairs::johnson(&grid, [(0, 0)]).unwrap();
+
+        assert_eq!(floyd_warshall.len(), 12);
+        for (from, state) in floyd_warshall.arena().iter() {
+            let expected = grid.cost_distances_from(*state, None);
+            for (to, other) in floyd_warshall.arena().iter() {
+                let distance = expected.distance(other);
+                assert_eq!(floyd_warshall.distance(from, to), distance);
+                assert_eq!(johnson.distance_between(state, other), distance);
+
+                let path = floyd_warshall.path_between(state, other).unwrap();
+                assert_eq!(Some(path.len()), johnson.path(from, to).map(|p| p.len()));
+                assert!(path
+                    .windows(2)
+                    .all(|w| grid.neighbours(&w[0]).contains(&w[1])));
+            }
+        }
+    }
+
+    /// A line of states going forward to the next one for -1 and back to
+    /// the first one for `back`.
+    struct Line {
+        n: u8,
+        back: f32,
+    }
+
+    impl StateSpace for Line {
+        type State = u8;
+
+        fn neighbours(&self, &state: &u8) -> Vec<u8> {
+            if state + 1 < self.n {
+                vec![state + 1, 0]
+            } else {
+                vec![0]
+            }
+        }
+    }
+
+    impl CostStateSpace for Line {
+        fn cost(&self, current: &u8, next: &u8) -> f32 {
+            if *next == current + 1 {
+                -1.
+            } else {
+                self.back
+            }
+        }
+    }
+
+    #[test]
+    fn negative_costs() {
+        let line = Line { n: 4, back: 5. };
+        let floyd_warshall = AllPairs::floyd_warshall(&line, [0]).unwrap();
+        let johnson = AllPairs::johnson(&line, [0]).unwrap();
+
+        for all_pairs in [floyd_warshall, johnson] {
+            assert_eq!(all_pairs.distance_between(&0, &3), Some(-3.));
+            assert_eq!(all_pairs.distance_between(&3, &2), Some(3.));
+            assert_eq!(all_pairs.path_between(&2, &1), Some(vec![2, 3, 0, 1]));
+            let (from, to) = (all_pairs.id(&1).unwrap(), all_pairs.id(&3).unwrap());
+            assert_eq!(all_pairs.next_hop(from, to), all_pairs.id(&2));
+        }
+    }
+
+    #[test]
+    fn negative_cycles() {
+        let line = Line { n: 4, back: 2. };
+
+        let cycle = AllPairs::floyd_warshall(&line, [0]).err().unwrap();
+        assert!(cycle.cost < 0.);
+        let cycle = AllPairs::johnson(&line, [0]).err().unwrap();
+        assert!(cycle.cost < 0.);
+    }
+}
@@ -0,0 +1,257 @@
+//! Interning of states into compact identifiers.
+//!
+//! A [`StateArena`] stores each state once and hands out a [`StateId`] for
+//! it, so bookkeeping structures can refer to states without cloning them.
+//! The hasher of the intern table is configurable, a faster non-cryptographic
+//! hasher (e.g. `FxHash` or `aHash`) paying off for large states.
+//!
+//! ```
+//! # use state_space::arena::StateArena;
+//! let mut arena = StateArena::new();
+//! let (a, inserted) = arena.intern("a");
+//! assert!(inserted);
+//!
+//! assert_eq!(arena.intern("a"), (a, false));
+//! assert_eq!(arena[a], "a");
+//! ```
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::ops::Index;
+
+/// The identifier of a state in a [`StateArena`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateId(u32);
+
+impl StateId {
+    #[inline]
+    pub(crate) fn new(index: usize) -> Self {
+        Self(u32::try_from(index).expect("Arena should hold at most u32::MAX states"))
+    }
+
+    /// Returns the index of the state in [`StateArena::states`].
+    #[inline]
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A store of states handing out a [`StateId`] for each of them.
+#[derive(Clone)]
+pub struct StateArena<T, H = RandomState> {
+    states: Vec<T>,
+    table: HashMap<u64, Slot, BuildHasherDefault<IdentityHasher>>,
+    hasher: H,
+}
+
+impl<T: Eq + Hash> StateArena<T> {
+    /// Creates an empty arena.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Eq + Hash> Default for StateArena<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, H> StateArena<T, H> {
+    /// Returns the number of states stored.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns whether no state is stored.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Returns the states stored, indexed by [`StateId::index`].
+    #[inline]
+    #[must_use]
+    pub fn states(&self) -> &[T] {
+        &self.states
+    }
+
+    /// Returns an iterator over the states along with their identifiers.
+    pub fn iter(&self) -> impl Iterator<Item = (StateId, &T)> {
+        self.states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (StateId::new(index), state))
+    }
+
+    /// Stores `state` without interning it, so it is not found by
+    /// [`StateArena::intern`] nor [`StateArena::get`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena holds more than `u32::MAX` states.
+    pub fn push(&mut self, state: T) -> StateId {
+        let id = StateId::new(self.states.len());
+        self.states.push(state);
+        id
+    }
+
+    /// Replaces the state `id`, stored with [`StateArena::push`], by `state`.
+    pub(crate) fn replace(&mut self, id: StateId, state: T) {
+        self.states[id.index()] = state;
+    }
+}
+
+impl<T: Eq + Hash, H: BuildHasher> StateArena<T, H> {
+    /// Creates an empty arena hashing states with `hasher`.
+    #[inline]
+    pub fn with_hasher(hasher: H) -> Self {
+        Self {
+            states: Vec::new(),
+            table: HashMap::default(),
+            hasher,
+        }
+    }
+
+    /// Returns the identifier of `state`, storing it if needed, along with
+    /// whether it has been stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena holds more than `u32::MAX` states.
+    pub fn intern(&mut self, state: T) -> (StateId, bool) {
+        let hash = self.hasher.hash_one(&state);
+
+        if let Some(id) = self.find(hash, &state) {
+            return (id, false);
+        }
+
+        let id = self.push(state);
+        match self.table.entry(hash) {
+            Entry::Vacant(entry) => {
+                entry.insert(Slot::One(id));
+            }
+            Entry::Occupied(mut entry) => {
+                let slot = entry.get_mut();
+                match slot {
+                    Slot::One(other) => *slot = Slot::Many(vec![*other, id]),
+                    Slot::Many(ids) => ids.push(id),
+                }
+            }
+        }
+
+        (id, true)
+    }
+
+    /// Returns the identifier of `state` if it has been interned.
+    pub fn get(&self, state: &T) -> Option<StateId> {
+        self.find(self.hasher.hash_one(state), state)
+    }
+
+    fn find(&self, hash: u64, state: &T) -> Option<StateId> {
+        match self.table.get(&hash)? {
+            Slot::One(id) => Some(*id).filter(|id| self.states[id.index()] == *state),
+            Slot::Many(ids) => ids
+                .iter()
+                .copied()
+                .find(|id| self.states[id.index()] == *state),
+        }
+    }
+}
+
+impl<T, H> Index<StateId> for StateArena<T, H> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, id: StateId) -> &Self::Output {
+        &self.states[id.index()]
+    }
+}
+
+/// The identifiers of the states sharing a hash.
+#[derive(Clone)]
+enum Slot {
+    One(StateId),
+    Many(Vec<StateId>),
+}
+
+/// A hasher passing through the precomputed hashes of the intern table.
+#[derive(Default)]
+struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 << 8) | u64::from(byte);
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hasher mapping every state to the same hash.
+    #[derive(Default)]
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn intern_once() {
+        let mut arena = StateArena::new();
+        let (a, _) = arena.intern((1, 2));
+        let (b, _) = arena.intern((3, 4));
+
+        assert_ne!(a, b);
+        assert_eq!(arena.intern((1, 2)), (a, false));
+        assert_eq!(arena.get(&(3, 4)), Some(b));
+        assert_eq!(arena.len(), 2);
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        let mut arena = StateArena::with_hasher(BuildHasherDefault::<ConstantHasher>::default());
+        let ids: Vec<_> = (0..10).map(|i| arena.intern(i).0).collect();
+
+        for (i, id) in ids.into_iter().enumerate() {
+            assert_eq!(arena.get(&i), Some(id));
+            assert_eq!(arena[id], i);
+        }
+    }
+
+    #[test]
+    fn pushed_states_are_not_interned() {
+        let mut arena = StateArena::new();
+        let a = arena.push('a');
+        let (b, inserted) = arena.intern('a');
+
+        assert!(inserted);
+        assert_ne!(a, b);
+    }
+}
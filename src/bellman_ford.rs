@@ -0,0 +1,272 @@
+//! Shortest paths in finite state spaces with negative transition costs.
+//!
+//! [`CostStateSpace::dijkstra`] and
+//! [`HeuristicStateSpace::a_star`](crate::HeuristicStateSpace::a_star)
+//! assume that costs are non-negative and may return suboptimal paths
+//! otherwise. [`bellman_ford`] relaxes the states reached until no distance
+//! improves, which handles negative costs as long as no cycle of negative
+//! cost is reachable, and returns such a cycle otherwise.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # struct Ladder;
+//! # impl StateSpace for Ladder {
+//! #     type State = u8;
+//! #     fn neighbours(&self, &state: &u8) -> Vec<u8> {
+//! #         if state < 4 { vec![state + 1, state + 2] } else { vec![] }
+//! #     }
+//! # }
+//! # impl CostStateSpace for Ladder {
+//! #     fn cost(&self, current: &u8, next: &u8) -> f32 {
+//! #         if next - current == 2 { 3. } else { -1. }
+//! #     }
+//! # }
+//! // Climbing one rung at a time is rewarded, skipping one costs.
+//! let distances = Ladder.bellman_ford(0).unwrap();
+//!
+//! assert_eq!(distances.distance(&4), Some(-4.));
+//! assert_eq!(distances.path_to(&4), Some(vec![0, 1, 2, 3, 4]));
+//! ```
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+use crate::arena::{StateArena, StateId};
+use crate::distance::DistanceMap;
+use crate::search::{SearchStats, Termination};
+use crate::CostStateSpace;
+
+/// A cycle of negative cost, along which paths can be made arbitrarily cheap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NegativeCycle<T> {
+    /// The states of the cycle, each leading to the next one and the last one
+    /// leading back to the first one.
+    pub states: Vec<T>,
+    /// The cost of going once around the cycle.
+    pub cost: f32,
+}
+
+impl<T> fmt::Display for NegativeCycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "negative cycle of {} states and cost {}",
+            self.states.len(),
+            self.cost
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for NegativeCycle<T> {}
+
+/// Returns the costs of the cheapest paths from `init` to every state
+/// reachable from it, along with their parents, with the queue-based
+/// Bellman-Ford algorithm (SPFA).
+///
+/// The state space reachable from `init` should be finite.
+///
+/// # Errors
+///
+/// Returns a cycle of negative cost if one is reachable from `init`.
+pub fn bellman_ford<S>(
+    space: &S,
+    init: S::State,
+) -> Result<DistanceMap<S::State>, NegativeCycle<S::State>>
+where
+    S: CostStateSpace + ?Sized,
+{
+    let mut arena = StateArena::new();
+    let (root, _) = arena.intern(init);
+    let mut distances = vec![(0., None)];
+    // The number of transitions of the current path to each state.
+    let mut lengths = vec![0];
+    let mut queued = vec![true];
+    let mut queue = VecDeque::from([root]);
+    let mut stats = SearchStats::default();
+
+    while let Some(id) = queue.pop_front() {
+        queued[id.index()] = false;
+        stats.expanded += 1;
+
+        let current = arena[id].clone();
+        let (distance, _) = distances[id.index()];
+        let length = lengths[id.index()] + 1;
+        let mut cycle = None;
+
+        space.for_each_neighbour(&current, &mut |neighbour| {
+            stats.generated += 1;
+
+            let cost = distance + space.cost(&current, &neighbour);
+            let (next, inserted) = arena.intern(neighbour);
+            if inserted {
+                distances.push((cost, Some(id)));
+                lengths.push(length);
+                queued.push(false);
+            } else if cost < distances[next.index()].0 {
+                distances[next.index()] = (cost, Some(id));
+                lengths[next.index()] = length;
+            } else {
+                return;
+            }
+
+            // A path with as many transitions as there are states goes
+            // through a state twice.
+            if length >= arena.len() && cycle.is_none() {
+                cycle = parent_cycle(&distances, next);
+            }
+            if !queued[next.index()] {
+                queued[next.index()] = true;
+                queue.push_back(next);
+            }
+        });
+        stats.max_open = stats.max_open.max(queue.len());
+
+        if let Some(cycle) = cycle {
+            let members: Vec<_> = cycle.into_iter().map(|id| arena[id].clone()).collect();
+            let cost = members
+                .iter()
+                .zip(members.iter().cycle().skip(1))
+                .map(|(current, next)| space.cost(current, next))
+                .sum();
+
+            return Err(NegativeCycle {
+                states: members,
+                cost,
+            });
+        }
+    }
+
+    Ok(DistanceMap::from_parts(
+        arena,
+        distances,
+        Termination::Exhausted,
+        stats,
+    ))
+}
+
+/// Returns the cycle formed by the parents from `id`, if any, in the order of
+/// the transitions.
+///
+/// Such a cycle always has a negative cost, as each state was last improved
+/// through its parent.
+fn parent_cycle(distances: &[(f32, Option<StateId>)], id: StateId) -> Option<Vec<StateId>> {
+    let mut visited = vec![false; distances.len()];
+
+    let mut current = id;
+    let start = loop {
+        if visited[current.index()] {
+            break current;
+        }
+        visited[current.index()] = true;
+        current = distances[current.index()].1?;
+    };
+
+    let mut cycle = vec![start];
+    let mut current = distances[start.index()].1?;
+    while current != start {
+        cycle.push(current);
+        current = distances[current.index()].1?;
+    }
+
+    cycle.reverse();
+    Some(cycle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+    use crate::StateSpace;
+
+    /// A grid where moving right is rewarded.
+    struct Downhill(Grid);
+
+    impl StateSpace for Downhill {
+        type State = (usize, usize);
+
+        fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
+            self.0.neighbours(state)
+        }
+    }
+
+    impl CostStateSpace for Downhill {
+        fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+            if next.0 > current.0 {
+                -1.
+            } else {
+                2.
+            }
+        }
+    }
+
+    /// A ring of `n` states, going forward costing `forward`.
+    struct Ring {
+        n: u8,
+        forward: f32,
+    }
+
+    impl StateSpace for Ring {
+        type State = u8;
+
+        fn neighbours(&self, state: &u8) -> Vec<u8> {
+            vec![(state + 1) % self.n, (state + self.n - 1) % self.n]
+        }
+    }
+
+    impl CostStateSpace for Ring {
+        fn cost(&self, current: &u8, next: &u8) -> f32 {
+            if *next == (current + 1) % self.n {
+                self.forward
+            } else {
+                2.
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_with_dijkstra_on_non_negative_costs() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+        let expected = grid.cost_distances_from((0, 0), None);
+        let distances = bellman_ford(&grid, (0, 0)).unwrap();
+
+        assert_eq!(distances.len(), expected.len());
+        for (state, distance, _) in expected.iter() {
+            assert_eq!(distances.distance(state), Some(distance));
+        }
+    }
+
+    #[test]
+    fn negative_costs() {
+        // Going right then back left costs 1, which is not a negative cycle.
+        let space = Downhill(Grid::open(4, 2));
+        let distances = space.bellman_ford((0, 0)).unwrap();
+
+        assert_eq!(distances.distance(&(3, 0)), Some(-3.));
+        assert_eq!(distances.distance(&(3, 1)), Some(-1.));
+        assert_eq!(distances.distance(&(0, 1)), Some(2.));
+        assert_eq!(
+            distances.path_to(&(3, 0)),
+            Some(vec![(0, 0), (1, 0), (2, 0), (3, 0)])
+        );
+    }
+
+    #[test]
+    fn negative_cycle_witness() {
+        let space = Ring { n: 5, forward: -1. };
+        let cycle = space.bellman_ford(0).err().unwrap();
+
+        assert_eq!(cycle.states.len(), 5);
+        assert!((cycle.cost + 5.).abs() < 1e-6);
+        for (i, state) in cycle.states.iter().enumerate() {
+            assert_eq!(cycle.states[(i + 1) % 5], (state + 1) % 5);
+        }
+
+        // Going forward then backward costs 1.5.
+        let space = Ring {
+            n: 5,
+            forward: -0.5,
+        };
+        assert!(space.bellman_ford(0).err().unwrap().cost < 0.);
+        assert!(Ring { n: 5, forward: 1. }.bellman_ford(0).is_ok());
+    }
+}
@@ -0,0 +1,562 @@
+//! Saving the state of a search to resume it later.
+//!
+//! A [`Checkpoint`] holds everything a search needs to go on: its open list,
+//! the states reached with their parents and costs, and its statistics. It is
+//! taken from the [`Progress`] given to an observer, and
+//! [`AutoCheckpoint`] does so periodically. The search is resumed with
+//! [`SearchBuilder::resume`](crate::search::SearchBuilder::resume).
+//!
+//! ```no_run
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # use state_space::algorithm::Dijkstra;
+//! # use state_space::checkpoint::{AutoCheckpoint, Checkpoint};
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = i32;
+//! #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+//! # }
+//! # impl CostStateSpace for Line {
+//! #     fn cost(&self, _: &i32, _: &i32) -> f32 { 1. }
+//! # }
+//! let result = match Checkpoint::load("search.checkpoint") {
+//!     Ok(checkpoint) => Line
+//!         .search()
+//!         .algorithm(Dijkstra)
+//!         .observer(AutoCheckpoint::new(100_000, |checkpoint: Checkpoint<i32>| {
+//!             checkpoint.save("search.checkpoint").unwrap();
+//!         }))
+//!         .resume(checkpoint),
+//!     Err(_) => Line
+//!         .search()
+//!         .algorithm(Dijkstra)
+//!         .observer(AutoCheckpoint::new(100_000, |checkpoint: Checkpoint<i32>| {
+//!             checkpoint.save("search.checkpoint").unwrap();
+//!         }))
+//!         .run(0, 1_000_000),
+//! };
+//! ```
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::FrontierKind;
+use crate::arena::StateArena;
+use crate::engine::{Frontier, Node, SearchState, Tree};
+use crate::search::{DuplicateDetection, Observer, Progress, SearchStats, Termination, TieBreak};
+use crate::visited::Visited;
+use crate::StateSpace;
+
+/// The state of a search between two expansions.
+///
+/// It can only be taken with the [`DuplicateDetection::None`],
+/// [`DuplicateDetection::Parent`], [`DuplicateDetection::Path`] and
+/// [`DuplicateDetection::ClosedSet`] strategies, the others returning
+/// [`UnsupportedCheckpoint`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint<T> {
+    goals: Vec<T>,
+    frontier: FrontierKind,
+    duplicate_detection: DuplicateDetection,
+    /// The states reached, indexed by the identifiers of the nodes.
+    states: Vec<T>,
+    nodes: Vec<NodeRecord>,
+    /// The nodes released, whose slots are reused.
+    free: Vec<usize>,
+    /// The best costs of the states with [`DuplicateDetection::ClosedSet`].
+    best_costs: Vec<f32>,
+    /// The open nodes along with their priorities, in insertion order.
+    open: Vec<(usize, f32)>,
+    stats: SearchStats,
+    pruned: Option<Termination>,
+}
+
+/// A node of the search tree, its state being an index in
+/// [`Checkpoint::states`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+struct NodeRecord {
+    state: usize,
+    parent: Option<usize>,
+    cost: f32,
+    depth: usize,
+}
+
+impl<T> Checkpoint<T> {
+    /// Returns the goals of the search, empty when exploring.
+    #[inline]
+    #[must_use]
+    pub fn goals(&self) -> &[T] {
+        &self.goals
+    }
+
+    /// Returns the order in which the search expands its open states.
+    #[inline]
+    #[must_use]
+    pub const fn frontier(&self) -> FrontierKind {
+        self.frontier
+    }
+
+    /// Returns the statistics of the search when the checkpoint was taken.
+    #[inline]
+    #[must_use]
+    pub const fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+}
+
+impl<T: Clone> Checkpoint<T> {
+    /// Takes a checkpoint of the search reporting `progress`, if its
+    /// duplicate detection strategy allows it.
+    pub(crate) fn take(progress: &Progress<'_, T>) -> Result<Self, UnsupportedCheckpoint> {
+        let (duplicate_detection, best_costs) = match progress.visited {
+            Visited::None => (DuplicateDetection::None, Vec::new()),
+            Visited::Parent => (DuplicateDetection::Parent, Vec::new()),
+            Visited::Path => (DuplicateDetection::Path, Vec::new()),
+            Visited::ClosedSet(best_costs) => (DuplicateDetection::ClosedSet, best_costs.clone()),
+            Visited::Transposition(_) | Visited::Bloom(_) | Visited::HashCompaction(..) => {
+                return Err(UnsupportedCheckpoint)
+            }
+        };
+
+        Ok(Self {
+            goals: progress.goals.to_vec(),
+            frontier: progress.frontier.kind(),
+            duplicate_detection,
+            states: progress.states.to_vec(),
+            nodes: progress
+                .nodes
+                .iter()
+                .map(|node| NodeRecord {
+                    state: node.state.index(),
+                    parent: node.parent,
+                    cost: node.cost,
+                    depth: node.depth,
+                })
+                .collect(),
+            free: progress.free.to_vec(),
+            best_costs,
+            open: progress.frontier.entries(),
+            stats: *progress.stats,
+            pruned: progress.pruned,
+        })
+    }
+}
+
+impl<T: Eq + Hash> Checkpoint<T> {
+    /// Checks that the checkpoint describes a search tree, as a file may be
+    /// corrupted.
+    fn validate(&self) -> Result<(), &'static str> {
+        if !matches!(
+            self.duplicate_detection,
+            DuplicateDetection::None
+                | DuplicateDetection::Parent
+                | DuplicateDetection::Path
+                | DuplicateDetection::ClosedSet
+        ) {
+            return Err("unsupported duplicate detection");
+        }
+        if self.duplicate_detection == DuplicateDetection::ClosedSet {
+            if self.best_costs.len() != self.states.len() {
+                return Err("best costs do not match the states");
+            }
+            let mut states = HashSet::with_capacity(self.states.len());
+            if !self.states.iter().all(|state| states.insert(state)) {
+                return Err("duplicate state in a closed set");
+            }
+        }
+
+        let mut released = vec![false; self.nodes.len()];
+        for &index in &self.free {
+            if index >= self.nodes.len() || released[index] {
+                return Err("invalid released node");
+            }
+            released[index] = true;
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.state >= self.states.len() {
+                return Err("node state out of range");
+            }
+            if released[index] {
+                continue;
+            }
+            // Depths decreasing along parents, the nodes form a forest.
+            match node.parent {
+                None if node.depth == 0 => {}
+                Some(parent)
+                    if parent < self.nodes.len()
+                        && !released[parent]
+                        && self.nodes[parent].depth + 1 == node.depth => {}
+                _ => return Err("invalid node parent"),
+            }
+        }
+        if self.nodes.first().is_some_and(|root| root.parent.is_some()) {
+            return Err("first node is not a root");
+        }
+
+        if self
+            .open
+            .iter()
+            .any(|&(index, _)| index >= self.nodes.len() || released[index])
+        {
+            return Err("invalid open node");
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Clone + Eq + Hash> Checkpoint<T> {
+    /// Rebuilds the state of the search, returning it along with its goals.
+    pub(crate) fn restore<H: BuildHasher>(
+        self,
+        tie_break: TieBreak,
+        hasher: H,
+    ) -> (SearchState<T, H>, Vec<T>) {
+        let mut arena = StateArena::with_hasher(hasher);
+        let interned = self.duplicate_detection == DuplicateDetection::ClosedSet;
+        let ids: Vec<_> = self
+            .states
+            .into_iter()
+            .map(|state| {
+                if interned {
+                    arena.intern(state).0
+                } else {
+                    arena.push(state)
+                }
+            })
+            .collect();
+
+        let mut nodes: Vec<_> = self
+            .nodes
+            .into_iter()
+            .map(|node| Node {
+                state: ids[node.state],
+                parent: node.parent,
+                cost: node.cost,
+                depth: node.depth,
+                children: 0,
+            })
+            .collect();
+
+        // The released nodes are not children of their former parents.
+        let mut released = vec![false; nodes.len()];
+        for &index in &self.free {
+            released[index] = true;
+        }
+        for index in 0..nodes.len() {
+            if let (false, Some(parent)) = (released[index], nodes[index].parent) {
+                nodes[parent].children += 1;
+            }
+        }
+
+        let mut frontier = Frontier::new(self.frontier, tie_break);
+        for (node, priority) in self.open {
+            frontier.push(node, priority, nodes[node].cost);
+        }
+
+        let visited = if interned {
+            Visited::ClosedSet(self.best_costs)
+        } else {
+            Visited::new(self.duplicate_detection)
+        };
+
+        let state = SearchState {
+            frontier,
+            visited,
+            arena,
+            tree: Tree {
+                nodes,
+                free: self.free,
+            },
+            stats: self.stats,
+            pruned: self.pruned,
+        };
+        (state, self.goals)
+    }
+}
+
+impl<T: Serialize> Checkpoint<T> {
+    /// Writes the checkpoint to the file at `path`.
+    ///
+    /// The checkpoint is first written next to it, with `.partial` appended
+    /// to its name, then moved, so that a previous checkpoint is kept if the
+    /// process is killed meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        // Appended rather than replacing the extension, so that `a.x` and
+        // `a.y` are written to different files.
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        bincode::serialize_into(&mut writer, self).map_err(into_io_error)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        fs::rename(partial, path)
+    }
+}
+
+impl<T: DeserializeOwned + Eq + Hash> Checkpoint<T> {
+    /// Reads a checkpoint from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or does not hold a valid
+    /// checkpoint.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let checkpoint: Self = bincode::deserialize_from(reader).map_err(into_io_error)?;
+        checkpoint
+            .validate()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        Ok(checkpoint)
+    }
+}
+
+#[allow(clippy::boxed_local)]
+fn into_io_error(error: bincode::Error) -> io::Error {
+    match *error {
+        bincode::ErrorKind::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+impl<T: Clone> Progress<'_, T> {
+    /// Takes a checkpoint of the search.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`DuplicateDetection`] strategy of the search
+    /// does not allow checkpoints.
+    #[inline]
+    pub fn checkpoint(&self) -> Result<Checkpoint<T>, UnsupportedCheckpoint> {
+        Checkpoint::take(self)
+    }
+}
+
+/// The error returned when taking a checkpoint of a search whose
+/// [`DuplicateDetection`] strategy does not allow it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnsupportedCheckpoint;
+
+impl fmt::Display for UnsupportedCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "checkpoints need the `None`, `Parent`, `Path` or `ClosedSet` duplicate detection",
+        )
+    }
+}
+
+impl Error for UnsupportedCheckpoint {}
+
+/// An observer taking a checkpoint every given number of expansions and
+/// handing it to a closure, for instance to save it.
+///
+/// The search panics when taking a checkpoint if its [`DuplicateDetection`]
+/// strategy does not allow it, see [`Progress::checkpoint`].
+#[derive(Debug, Clone)]
+pub struct AutoCheckpoint<F> {
+    every: usize,
+    save: F,
+}
+
+impl<F> AutoCheckpoint<F> {
+    /// Creates an observer calling `save` with a checkpoint every `every`
+    /// expansions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    #[inline]
+    pub fn new(every: usize, save: F) -> Self {
+        assert!(
+            every > 0,
+            "Checkpoints should be taken every few expansions"
+        );
+
+        Self { every, save }
+    }
+}
+
+impl<S, F> Observer<S> for AutoCheckpoint<F>
+where
+    S: StateSpace + ?Sized,
+    F: FnMut(Checkpoint<S::State>),
+{
+    fn on_expand(&mut self, _space: &S, progress: &Progress<'_, S::State>) {
+        if progress.stats.expanded % self.every == 0 {
+            match progress.checkpoint() {
+                Ok(checkpoint) => (self.save)(checkpoint),
+                Err(error) => panic!("{}", error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{AStar, BreadthFirstSearch, DepthFirstSearch, Dijkstra};
+    use crate::search::{Limits, NoObserver};
+    use crate::test_utils::Grid;
+
+    fn grid() -> Grid {
+        Grid::from_rows(&[
+            "..........",
+            ".XXXXXXXX.",
+            ".X......X.",
+            ".X.XXXX.X.",
+            ".X.X..X.X.",
+            ".X.X.XX.X.",
+            "...X......",
+        ])
+    }
+
+    /// Returns the checkpoint taken after `expansions` expansions.
+    fn checkpoint_after<A>(algorithm: A, expansions: usize) -> Checkpoint<(usize, usize)>
+    where
+        A: crate::algorithm::SearchAlgorithm<Grid>,
+    {
+        let mut checkpoint = None;
+        grid()
+            .search()
+            .algorithm(algorithm)
+            .observer(AutoCheckpoint::new(expansions, |c| {
+                checkpoint.get_or_insert(c);
+            }))
+            .run((0, 0), (4, 4));
+
+        checkpoint.unwrap()
+    }
+
+    #[test]
+    fn resumed_search_matches_uninterrupted_one() {
+        let grid = grid();
+
+        let expected = grid.search().algorithm(AStar).run((0, 0), (4, 4));
+        let resumed = grid
+            .search()
+            .algorithm(AStar)
+            .observer(NoObserver)
+            .resume(checkpoint_after(AStar, 10));
+
+        assert_eq!(resumed, expected);
+
+        let expected = grid.search().algorithm(Dijkstra).run((0, 0), (4, 4));
+        let resumed = grid
+            .search()
+            .algorithm(Dijkstra)
+            .resume(checkpoint_after(Dijkstra, 7));
+
+        assert_eq!(resumed, expected);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "state-space-checkpoint-test-{}",
+            std::process::id()
+        ));
+        let checkpoint = checkpoint_after(BreadthFirstSearch, 5);
+
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(loaded.stats().expanded, 5);
+        assert_eq!(
+            grid().search().resume(loaded).cost,
+            grid().search().run((0, 0), (4, 4)).cost
+        );
+    }
+
+    #[test]
+    fn resumed_tree_search_matches_uninterrupted_one() {
+        let grid = Grid::open(5, 5);
+        let mut checkpoint = None;
+        let search = || {
+            grid.search()
+                .algorithm(DepthFirstSearch)
+                .duplicate_detection(DuplicateDetection::Path)
+                .limits(Limits {
+                    max_depth: Some(7),
+                    ..Limits::default()
+                })
+        };
+        let expected = search()
+            .observer(AutoCheckpoint::new(100, |c| {
+                checkpoint.get_or_insert(c);
+            }))
+            .run((0, 0), (4, 4));
+
+        let checkpoint = checkpoint.unwrap();
+        // Some nodes have been released and their slots are to be reused.
+        assert!(!checkpoint.free.is_empty());
+        assert_eq!(search().resume(checkpoint), expected);
+    }
+
+    #[test]
+    fn no_checkpoint_with_probabilistic_detection() {
+        let mut errors = Vec::new();
+        grid()
+            .search()
+            .duplicate_detection(DuplicateDetection::HashCompaction)
+            .observer(|progress: &Progress<'_, (usize, usize)>| {
+                errors.push(progress.checkpoint().unwrap_err());
+            })
+            .run((0, 0), (4, 4));
+
+        assert!(!errors.is_empty());
+        assert!(errors.iter().all(|&error| error == UnsupportedCheckpoint));
+    }
+
+    #[test]
+    #[should_panic(expected = "checkpoints need")]
+    fn auto_checkpoint_panics_with_transpositions() {
+        grid()
+            .search()
+            .duplicate_detection(DuplicateDetection::Transposition(16))
+            .observer(AutoCheckpoint::new(1, |_| {}))
+            .run((0, 0), (4, 4));
+    }
+
+    #[test]
+    fn corrupt_checkpoint_is_rejected() {
+        type Corruption = fn(&mut Checkpoint<(usize, usize)>);
+
+        let path = std::env::temp_dir().join(format!(
+            "state-space-corrupt-checkpoint-test-{}",
+            std::process::id()
+        ));
+        let corruptions: [Corruption; 4] = [
+            |c| c.nodes[3].parent = Some(1000),
+            |c| c.nodes[3].state = 1000,
+            |c| c.open[0].0 = 1000,
+            |c| c.best_costs.truncate(1),
+        ];
+
+        for corrupt in &corruptions {
+            let mut checkpoint = checkpoint_after(BreadthFirstSearch, 5);
+            corrupt(&mut checkpoint);
+            checkpoint.save(&path).unwrap();
+
+            let error = Checkpoint::<(usize, usize)>::load(&path).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,612 @@
+//! Compact encodings of states.
+//!
+//! A [`StateCodec`] writes a state as a sequence of bits, either of a fixed
+//! length known from its type, for instance to store it as a record of
+//! [`ExternalSearch`](crate::external::ExternalSearch), or of a length
+//! varying with the state.
+//!
+//! States being permutations of `0..N` can be encoded by their rank among
+//! the `N!` permutations with [`Permutation`], a perfect hash taking
+//! `log2(N!)` bits. Sequences of `k` distinct elements of `0..n` are ranked
+//! among the `n!/(n-k)!` such sequences by [`rank_partial`].
+//!
+//! ```
+//! # use state_space::codec::{Permutation, StateCodec};
+//! assert_eq!(<(usize, usize)>::BITS, Some(128));
+//! assert_eq!((3_usize, 4_usize).to_bytes().len(), 16);
+//!
+//! let puzzle = Permutation([1, 0, 2, 3, 4, 5, 6, 7, 8]);
+//! assert_eq!(Permutation::<9>::BITS, Some(19));
+//! assert_eq!(Permutation::from_bytes(&puzzle.to_bytes()), Some(puzzle));
+//! ```
+use std::convert::TryFrom;
+
+/// An encoding of a state into bits.
+///
+/// Two states must be equal if and only if their encodings are.
+pub trait StateCodec: Sized {
+    /// The number of bits of every encoded state, `None` if it varies from
+    /// one state to another.
+    const BITS: Option<usize>;
+
+    /// Appends the encoding of the state to `writer`.
+    fn encode(&self, writer: &mut BitWriter);
+
+    /// Reads a state from `reader`, returning `None` if its bits do not
+    /// encode one.
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self>;
+
+    /// Returns the encoding of the state, padded with zeros to whole bytes.
+    #[inline]
+    #[must_use]
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        self.encode(&mut writer);
+        writer.into_bytes()
+    }
+
+    /// Reads a state from the bytes returned by [`StateCodec::to_bytes`].
+    #[inline]
+    #[must_use]
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::decode(&mut BitReader::new(bytes))
+    }
+}
+
+/// Returns the number of bytes of the states encoded by `T` if it is fixed.
+#[inline]
+#[must_use]
+pub fn fixed_bytes<T: StateCodec>() -> Option<usize> {
+    T::BITS.map(|bits| bits.div_ceil(8))
+}
+
+/// A buffer of bits, filled from the most significant bit of each byte.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    /// Creates an empty buffer.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of bits written.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no bit has been written.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends the `bits` lowest bits of `value`, most significant first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 64.
+    pub fn write(&mut self, value: u64, bits: u32) {
+        assert!(bits <= 64, "At most 64 bits should be written at once");
+
+        let mut remaining = bits;
+        while remaining > 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = (self.len % 8) as u32;
+            if offset == 0 {
+                self.bytes.push(0);
+            }
+
+            let free = 8 - offset;
+            let taken = free.min(remaining);
+            #[allow(clippy::cast_possible_truncation)]
+            let chunk = ((value >> (remaining - taken)) & ((1 << taken) - 1)) as u8;
+            if let Some(byte) = self.bytes.last_mut() {
+                *byte |= chunk << (free - taken);
+            }
+
+            remaining -= taken;
+            self.len += taken as usize;
+        }
+    }
+
+    /// Appends a single bit.
+    #[inline]
+    pub fn write_bool(&mut self, bit: bool) {
+        self.write(u64::from(bit), 1);
+    }
+
+    /// Appends `value` in as few bytes as possible, 7 bits at a time.
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.write((value & 0x7f) | 0x80, 8);
+            value >>= 7;
+        }
+        self.write(value, 8);
+    }
+
+    /// Returns the bits written, padded with zeros to whole bytes.
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// A reader of the bits of a [`BitWriter`].
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader starting at the first bit of `bytes`.
+    #[inline]
+    #[must_use]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    /// Returns the number of bits read.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Reads `bits` bits, returning `None` if fewer remain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 64.
+    pub fn read(&mut self, bits: u32) -> Option<u64> {
+        assert!(bits <= 64, "At most 64 bits should be read at once");
+        if self.position + bits as usize > 8 * self.bytes.len() {
+            return None;
+        }
+
+        let mut value = 0;
+        let mut remaining = bits;
+        while remaining > 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = (self.position % 8) as u32;
+            let available = 8 - offset;
+            let taken = available.min(remaining);
+
+            let byte = u64::from(self.bytes[self.position / 8]);
+            let chunk = (byte >> (available - taken)) & ((1 << taken) - 1);
+            value = (value << taken) | chunk;
+
+            remaining -= taken;
+            self.position += taken as usize;
+        }
+
+        Some(value)
+    }
+
+    /// Reads a single bit.
+    #[inline]
+    pub fn read_bool(&mut self) -> Option<bool> {
+        self.read(1).map(|bit| bit == 1)
+    }
+
+    /// Reads a value written with [`BitWriter::write_varint`].
+    pub fn read_varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read(8)?;
+            value |= (byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+macro_rules! impl_codec {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl StateCodec for $t {
+                const BITS: Option<usize> = Some(<$u>::BITS as usize);
+
+                #[inline]
+                #[allow(clippy::cast_sign_loss, clippy::cast_lossless)]
+                fn encode(&self, writer: &mut BitWriter) {
+                    writer.write(*self as $u as u64, <$u>::BITS);
+                }
+
+                #[inline]
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+                    reader.read(<$u>::BITS).map(|value| value as $u as Self)
+                }
+            }
+        )*
+    };
+}
+
+impl_codec!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => u64,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => u64
+);
+
+impl StateCodec for bool {
+    const BITS: Option<usize> = Some(1);
+
+    #[inline]
+    fn encode(&self, writer: &mut BitWriter) {
+        writer.write_bool(*self);
+    }
+
+    #[inline]
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        reader.read_bool()
+    }
+}
+
+const fn sum_bits(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        _ => None,
+    }
+}
+
+impl<A: StateCodec, B: StateCodec> StateCodec for (A, B) {
+    const BITS: Option<usize> = sum_bits(A::BITS, B::BITS);
+
+    #[inline]
+    fn encode(&self, writer: &mut BitWriter) {
+        self.0.encode(writer);
+        self.1.encode(writer);
+    }
+
+    #[inline]
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        Some((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+impl<A: StateCodec, B: StateCodec, C: StateCodec> StateCodec for (A, B, C) {
+    const BITS: Option<usize> = sum_bits(sum_bits(A::BITS, B::BITS), C::BITS);
+
+    #[inline]
+    fn encode(&self, writer: &mut BitWriter) {
+        self.0.encode(writer);
+        self.1.encode(writer);
+        self.2.encode(writer);
+    }
+
+    #[inline]
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        Some((A::decode(reader)?, B::decode(reader)?, C::decode(reader)?))
+    }
+}
+
+impl<T: StateCodec, const N: usize> StateCodec for [T; N] {
+    const BITS: Option<usize> = match T::BITS {
+        Some(bits) => Some(N * bits),
+        None => None,
+    };
+
+    #[inline]
+    fn encode(&self, writer: &mut BitWriter) {
+        for element in self {
+            element.encode(writer);
+        }
+    }
+
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        let elements = (0..N)
+            .map(|_| T::decode(reader))
+            .collect::<Option<Vec<_>>>()?;
+        Self::try_from(elements).ok()
+    }
+}
+
+/// A sequence encoded after its length.
+impl<T: StateCodec> StateCodec for Vec<T> {
+    const BITS: Option<usize> = None;
+
+    fn encode(&self, writer: &mut BitWriter) {
+        writer.write_varint(self.len() as u64);
+        for element in self {
+            element.encode(writer);
+        }
+    }
+
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        let len = usize::try_from(reader.read_varint()?).ok()?;
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+}
+
+/// A permutation of `0..N`, encoded by its rank among the `N!` permutations
+/// in lexicographic order.
+///
+/// Ranks are 64-bit so `N` is at most 20.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Permutation<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> Permutation<N> {
+    /// The number of permutations of `0..N`.
+    pub const COUNT: u64 = factorial(N);
+
+    /// Returns the rank of the permutation in lexicographic order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the elements are not a permutation of `0..N`.
+    #[inline]
+    #[must_use]
+    pub fn rank(&self) -> u64 {
+        rank(&self.0)
+    }
+
+    /// Returns the permutation of rank `rank` in lexicographic order, if
+    /// lower than [`Permutation::COUNT`].
+    #[must_use]
+    pub fn unrank(rank: u64) -> Option<Self> {
+        if rank >= Self::COUNT {
+            return None;
+        }
+
+        let mut elements = [0; N];
+        unrank(rank, &mut elements);
+        Some(Self(elements))
+    }
+}
+
+impl<const N: usize> StateCodec for Permutation<N> {
+    const BITS: Option<usize> = Some(bits_for(Self::COUNT));
+
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn encode(&self, writer: &mut BitWriter) {
+        writer.write(self.rank(), bits_for(Self::COUNT) as u32);
+    }
+
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn decode(reader: &mut BitReader<'_>) -> Option<Self> {
+        Self::unrank(reader.read(bits_for(Self::COUNT) as u32)?)
+    }
+}
+
+const fn factorial(n: usize) -> u64 {
+    assert!(n <= 20, "Permutations should have at most 20 elements");
+
+    let mut factorial = 1;
+    let mut i = 2;
+    while i <= n {
+        factorial *= i as u64;
+        i += 1;
+    }
+    factorial
+}
+
+/// Returns the number of bits needed to write the numbers below `count`.
+const fn bits_for(count: u64) -> usize {
+    if count <= 1 {
+        0
+    } else {
+        (u64::BITS - (count - 1).leading_zeros()) as usize
+    }
+}
+
+/// Returns the rank of the permutation of `0..elements.len()` in
+/// lexicographic order, through its Lehmer code.
+///
+/// # Panics
+///
+/// Panics if `elements` is not a permutation of `0..elements.len()` with at
+/// most 20 elements.
+#[must_use]
+pub fn rank(elements: &[u8]) -> u64 {
+    let n = elements.len();
+    assert!(n <= 20, "Permutations should have at most 20 elements");
+
+    let mut seen = 0_u32;
+    let mut rank = 0;
+    for (i, &element) in elements.iter().enumerate() {
+        assert!(
+            usize::from(element) < n && seen & (1 << element) == 0,
+            "Elements should be a permutation"
+        );
+
+        // The number of unused elements lower than this one.
+        let lower = u64::from(element) - u64::from((seen & ((1 << element) - 1)).count_ones());
+        rank += lower * factorial(n - 1 - i);
+        seen |= 1 << element;
+    }
+
+    rank
+}
+
+/// Writes to `elements` the permutation of `0..elements.len()` of rank
+/// `rank` in lexicographic order.
+///
+/// # Panics
+///
+/// Panics if `elements` has more than 20 elements or `rank` is not lower
+/// than the number of permutations.
+pub fn unrank(mut rank: u64, elements: &mut [u8]) {
+    let n = elements.len();
+    assert!(rank < factorial(n), "Rank should be lower than n!");
+
+    #[allow(clippy::cast_possible_truncation)]
+    let mut unused: Vec<u8> = (0..n).map(|element| element as u8).collect();
+    for (i, element) in elements.iter_mut().enumerate() {
+        let weight = factorial(n - 1 - i);
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (rank / weight) as usize;
+        rank %= weight;
+
+        *element = unused.remove(index);
+    }
+}
+
+/// Returns the number of sequences of `k` distinct elements of `0..n`,
+/// `n!/(n-k)!`, if it fits in 64 bits.
+#[must_use]
+pub fn partial_permutations(n: usize, k: usize) -> Option<u64> {
+    if k > n {
+        return Some(0);
+    }
+
+    (n - k + 1..=n).try_fold(1_u64, |count, factor| {
+        count.checked_mul(u64::try_from(factor).ok()?)
+    })
+}
+
+/// Returns the rank of `elements`, distinct elements of `0..n`, among the
+/// sequences of as many distinct elements in lexicographic order.
+///
+/// # Panics
+///
+/// Panics if `n` is greater than 256, if the elements are not distinct
+/// elements of `0..n`, or if their sequences are not counted by
+/// [`partial_permutations`].
+#[must_use]
+pub fn rank_partial(elements: &[u8], n: usize) -> u64 {
+    assert!(n <= 256, "Elements should be bytes");
+    assert!(
+        partial_permutations(n, elements.len()).is_some(),
+        "Partial permutations should be counted in 64 bits"
+    );
+
+    let mut seen = [0_u64; 4];
+    let mut rank = 0;
+    for (i, &element) in elements.iter().enumerate() {
+        let (word, bit) = (usize::from(element / 64), element % 64);
+        assert!(
+            usize::from(element) < n && seen[word] & (1 << bit) == 0,
+            "Elements should be distinct elements of 0..n"
+        );
+
+        // The number of unused elements lower than this one, a digit in
+        // base n - i.
+        let used: u32 = seen[..word].iter().map(|w| w.count_ones()).sum::<u32>()
+            + (seen[word] & ((1 << bit) - 1)).count_ones();
+        let lower = u64::from(element) - u64::from(used);
+        rank = rank * (n - i) as u64 + lower;
+        seen[word] |= 1 << bit;
+    }
+
+    rank
+}
+
+/// Writes to `elements` the sequence of distinct elements of `0..n` of rank
+/// `rank`, as returned by [`rank_partial`].
+///
+/// # Panics
+///
+/// Panics if `n` is greater than 256, or if `rank` is not lower than the
+/// number of sequences.
+pub fn unrank_partial(mut rank: u64, n: usize, elements: &mut [u8]) {
+    assert!(n <= 256, "Elements should be bytes");
+    let count = partial_permutations(n, elements.len());
+    assert!(
+        count.is_some_and(|count| rank < count),
+        "Rank should be lower than n!/(n-k)!"
+    );
+
+    // The digits are the ranks among the unused elements, least significant
+    // last.
+    for (i, element) in elements.iter_mut().enumerate().rev() {
+        let base = (n - i) as u64;
+        #[allow(clippy::cast_possible_truncation)]
+        let digit = (rank % base) as u8;
+        *element = digit;
+        rank /= base;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let mut unused: Vec<u8> = (0..n).map(|element| element as u8).collect();
+    for element in elements.iter_mut() {
+        *element = unused.remove(usize::from(*element));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_across_bytes() {
+        let mut writer = BitWriter::new();
+        writer.write(0b101, 3);
+        writer.write(0x1ff, 9);
+        writer.write_bool(true);
+        writer.write(u64::MAX, 64);
+        assert_eq!(writer.len(), 77);
+
+        let bytes = writer.into_bytes();
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(reader.read(3), Some(0b101));
+        assert_eq!(reader.read(9), Some(0x1ff));
+        assert_eq!(reader.read_bool(), Some(true));
+        assert_eq!(reader.read(64), Some(u64::MAX));
+        assert_eq!(reader.read(8), None);
+    }
+
+    #[test]
+    fn round_trips() {
+        let knight = (-2_isize, 7_isize);
+        assert_eq!(
+            <(isize, isize)>::from_bytes(&knight.to_bytes()),
+            Some(knight)
+        );
+
+        let flags = [true, false, true, true, false];
+        assert_eq!(flags.to_bytes().len(), 1);
+        assert_eq!(<[bool; 5]>::from_bytes(&flags.to_bytes()), Some(flags));
+
+        let path = vec![(1_u8, 2_u16), (255, 65535)];
+        assert_eq!(<Vec<(u8, u16)>>::BITS, None);
+        assert_eq!(Vec::from_bytes(&path.to_bytes()), Some(path));
+    }
+
+    #[test]
+    fn permutation_ranks_are_perfect() {
+        let mut ranks: Vec<_> = (0..Permutation::<4>::COUNT)
+            .map(|rank| Permutation::<4>::unrank(rank).unwrap())
+            .map(|permutation| permutation.rank())
+            .collect();
+        ranks.dedup();
+
+        assert_eq!(ranks, (0..24).collect::<Vec<_>>());
+        assert_eq!(Permutation([0, 1, 2, 3]).rank(), 0);
+        assert_eq!(Permutation([3, 2, 1, 0]).rank(), 23);
+        assert_eq!(Permutation::<4>::unrank(24), None);
+    }
+
+    #[test]
+    fn partial_permutation_ranks_are_perfect() {
+        assert_eq!(partial_permutations(9, 5), Some(15_120));
+        assert_eq!(partial_permutations(256, 9), None);
+        assert_eq!(partial_permutations(3, 4), Some(0));
+
+        let mut elements = [0; 3];
+        for rank in 0..partial_permutations(6, 3).unwrap() {
+            unrank_partial(rank, 6, &mut elements);
+            assert_eq!(rank_partial(&elements, 6), rank);
+        }
+        assert_eq!(rank_partial(&[0, 1, 2], 6), 0);
+        assert_eq!(rank_partial(&[5, 4, 3], 6), 119);
+        assert_eq!(rank_partial(&[255, 0], 256), 255 * 255);
+        assert_eq!(Permutation::<4>::BITS, Some(5));
+    }
+}
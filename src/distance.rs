@@ -0,0 +1,295 @@
+//! Distances from a state to every state reachable from it.
+//!
+//! A [`DistanceMap`] is returned by [`StateSpace::distances_from`],
+//! [`CostStateSpace::cost_distances_from`] and
+//! [`SearchBuilder::distances`](crate::search::SearchBuilder::distances).
+//!
+//! ```
+//! # use state_space::StateSpace;
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = i32;
+//! #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+//! # }
+//! let distances = Line.distances_from(0, Some(3));
+//!
+//! assert_eq!(distances.len(), 7);
+//! assert_eq!(distances.distance(&-2), Some(2.));
+//! assert_eq!(distances.distance(&4), None);
+//! assert_eq!(distances.path_to(&3), Some(vec![0, 1, 2, 3]));
+//! ```
+//!
+//! [`StateSpace::distances_from`]: crate::StateSpace::distances_from
+//! [`CostStateSpace::cost_distances_from`]: crate::CostStateSpace::cost_distances_from
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+use crate::arena::{StateArena, StateId};
+use crate::engine::{SearchState, Tree};
+use crate::search::{SearchStats, Termination};
+
+/// The distances of the states reached by a search along with their parents
+/// on a shortest path.
+///
+/// A search from several sources also labels each state with the source
+/// closest to it, partitioning the states like a Voronoi diagram.
+#[derive(Clone)]
+pub struct DistanceMap<T, H = RandomState> {
+    arena: StateArena<T, H>,
+    /// The entries indexed by the identifiers of the states.
+    entries: Vec<Entry>,
+    termination: Termination,
+    stats: SearchStats,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    distance: f32,
+    parent: Option<StateId>,
+    source: StateId,
+}
+
+impl<T, H> DistanceMap<T, H> {
+    /// Builds the map from the state of a search whose states are interned.
+    pub(crate) fn new(state: SearchState<T, H>, termination: Termination) -> Self {
+        let SearchState {
+            arena,
+            tree: Tree { nodes, .. },
+            stats,
+            ..
+        } = state;
+
+        // States reopened through cheaper paths have several nodes.
+        let mut best = vec![None; arena.len()];
+        // The roots of the nodes, a parent always preceding its children.
+        let mut roots = Vec::with_capacity(nodes.len());
+        for (index, node) in nodes.iter().enumerate() {
+            let slot: &mut Option<usize> = &mut best[node.state.index()];
+            if slot.is_none_or(|other| node.cost < nodes[other].cost) {
+                *slot = Some(index);
+            }
+            roots.push(node.parent.map_or(index, |parent| roots[parent]));
+        }
+
+        let entries = best
+            .into_iter()
+            .map(|index| {
+                let index = index.expect("Every state should have a node");
+                let node = &nodes[index];
+                Entry {
+                    distance: node.cost,
+                    parent: node.parent.map(|parent| nodes[parent].state),
+                    source: nodes[roots[index]].state,
+                }
+            })
+            .collect();
+
+        Self {
+            arena,
+            entries,
+            termination,
+            stats,
+        }
+    }
+
+    /// Builds the map from the distances and parents of the states reached
+    /// from a single source, the first state of `arena`.
+    pub(crate) fn from_parts(
+        arena: StateArena<T, H>,
+        distances: Vec<(f32, Option<StateId>)>,
+        termination: Termination,
+        stats: SearchStats,
+    ) -> Self {
+        let source = arena.iter().next().map(|(id, _)| id);
+        let entries = distances
+            .into_iter()
+            .map(|(distance, parent)| Entry {
+                distance,
+                parent,
+                source: source.expect("Source should be in the arena"),
+            })
+            .collect();
+
+        Self {
+            arena,
+            entries,
+            termination,
+            stats,
+        }
+    }
+
+    /// Returns the number of states reached.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns whether no state has been reached, which never happens as the
+    /// initial state is.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+
+    /// Returns why the search stopped, the distances being upper bounds if it
+    /// stopped before reaching every state within the cutoff.
+    #[inline]
+    #[must_use]
+    pub const fn termination(&self) -> Termination {
+        self.termination
+    }
+
+    /// Returns the statistics of the search.
+    #[inline]
+    #[must_use]
+    pub const fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Returns an iterator over the states reached along with their distances
+    /// and parents.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, f32, Option<&T>)> {
+        self.arena
+            .iter()
+            .zip(&self.entries)
+            .map(move |((_, state), entry)| {
+                let parent = entry.parent.map(|parent| &self.arena[parent]);
+                (state, entry.distance, parent)
+            })
+    }
+}
+
+impl<T: Eq + Hash, H: BuildHasher> DistanceMap<T, H> {
+    /// Returns the distance and the parent of `state`, if reached.
+    #[must_use]
+    pub fn get(&self, state: &T) -> Option<(f32, Option<&T>)> {
+        let entry = self.entries[self.arena.get(state)?.index()];
+        Some((
+            entry.distance,
+            entry.parent.map(|parent| &self.arena[parent]),
+        ))
+    }
+
+    /// Returns whether `state` has been reached.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, state: &T) -> bool {
+        self.arena.get(state).is_some()
+    }
+
+    /// Returns the distance of `state`, if reached.
+    #[inline]
+    #[must_use]
+    pub fn distance(&self, state: &T) -> Option<f32> {
+        self.get(state).map(|(distance, _)| distance)
+    }
+
+    /// Returns the parent of `state` on a shortest path, if it has been
+    /// reached and is not the initial state.
+    #[inline]
+    #[must_use]
+    pub fn parent(&self, state: &T) -> Option<&T> {
+        self.get(state).and_then(|(_, parent)| parent)
+    }
+
+    /// Returns the source from which `state` has been reached, the closest
+    /// one to it, if reached.
+    #[inline]
+    #[must_use]
+    pub fn source(&self, state: &T) -> Option<&T> {
+        let entry = self.entries[self.arena.get(state)?.index()];
+        Some(&self.arena[entry.source])
+    }
+}
+
+impl<T: Clone + Eq + Hash, H: BuildHasher> DistanceMap<T, H> {
+    /// Returns a shortest path from the initial state to `state`, if reached.
+    #[must_use]
+    pub fn path_to(&self, state: &T) -> Option<Vec<T>> {
+        let mut path = Vec::new();
+
+        let mut current = Some(self.arena.get(state)?);
+        while let Some(id) = current {
+            path.push(self.arena[id].clone());
+            current = self.entries[id.index()].parent;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns a map from the states reached to their distances and parents.
+    #[must_use]
+    pub fn into_map(self) -> HashMap<T, (f32, Option<T>)> {
+        self.iter()
+            .map(|(state, distance, parent)| (state.clone(), (distance, parent.cloned())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::Dijkstra;
+    use crate::test_utils::Grid;
+    use crate::{CostStateSpace, StateSpace};
+
+    #[test]
+    fn flood_fill() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+        let distances = grid.distances_from((0, 0), None);
+
+        assert_eq!(distances.len(), 12);
+        assert_eq!(distances.distance(&(3, 3)), Some(6.));
+        assert_eq!(distances.distance(&(1, 1)), None);
+
+        let path = distances.path_to(&(3, 3)).unwrap();
+        assert_eq!(path.len(), 7);
+        assert_eq!(distances.parent(&(3, 3)), Some(&path[5]));
+        assert_eq!(distances.parent(&(0, 0)), None);
+    }
+
+    #[test]
+    fn cutoff() {
+        let grid = Grid::open(10, 10);
+        let distances = grid.cost_distances_from((0, 0), Some(2.));
+
+        assert_eq!(distances.len(), 6);
+        assert!(distances.iter().all(|(_, distance, _)| distance <= 2.));
+        assert_eq!(
+            distances.into_map().get(&(1, 1)).map(|entry| entry.0),
+            Some(2.)
+        );
+    }
+
+    #[test]
+    fn agrees_with_searches() {
+        let grid = Grid::from_rows(&[
+            "..........",
+            ".XXXXXXXX.",
+            ".X......X.",
+            ".X.XXXX.X.",
+            "...X......",
+        ]);
+        let distances = grid.cost_distances_from((0, 0), None);
+
+        for (state, distance, _) in distances.iter() {
+            let result = grid.search().algorithm(Dijkstra).run((0, 0), *state);
+            assert_eq!(Some(distance), result.cost);
+        }
+    }
+
+    #[test]
+    fn voronoi() {
+        let grid = Grid::open(9, 3);
+        let distances = grid.voronoi(vec![(0, 1), (8, 1)], None);
+
+        assert_eq!(distances.len(), 27);
+        assert_eq!(distances.source(&(3, 0)), Some(&(0, 1)));
+        assert_eq!(distances.source(&(6, 2)), Some(&(8, 1)));
+        assert_eq!(distances.distance(&(6, 2)), Some(3.));
+        assert_eq!(distances.path_to(&(7, 1)), Some(vec![(8, 1), (7, 1)]));
+    }
+}
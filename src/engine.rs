@@ -0,0 +1,496 @@
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::collections::VecDeque;
+use std::hash::BuildHasher;
+use std::time::Instant;
+
+use crate::algorithm::{FrontierKind, SearchAlgorithm};
+use crate::arena::{StateArena, StateId};
+use crate::priority_queue::MinPrioriyQueue;
+use crate::search::{
+    CancellationToken, DuplicateDetection, Limits, Observer, Progress, SearchResult, SearchStats,
+    Termination, TieBreak,
+};
+use crate::visited::{Admitted, Visited};
+use crate::StateSpace;
+
+/// The configuration of a search besides its algorithm.
+#[derive(Clone)]
+pub struct Config {
+    pub limits: Limits,
+    pub tie_break: TieBreak,
+    pub duplicate_detection: DuplicateDetection,
+    pub cancellation: Option<CancellationToken>,
+    /// Whether negative transition costs are reported in debug builds.
+    pub check_costs: bool,
+}
+
+impl Config {
+    /// Returns why the search should stop before its next expansion, if it
+    /// should.
+    fn interruption(&self, start: Instant, stats: &SearchStats) -> Option<Termination> {
+        if self
+            .limits
+            .max_expansions
+            .is_some_and(|max| stats.expanded >= max)
+        {
+            Some(Termination::ExpansionLimit)
+        } else if self
+            .limits
+            .time_limit
+            .is_some_and(|limit| start.elapsed() >= limit)
+        {
+            Some(Termination::TimeLimit)
+        } else if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            Some(Termination::Cancelled)
+        } else {
+            None
+        }
+    }
+}
+
+/// A state reached by the search along with the path leading to it.
+pub struct Node {
+    pub state: StateId,
+    pub parent: Option<usize>,
+    pub cost: f32,
+    pub depth: usize,
+    /// The number of children kept, which lead to open nodes.
+    pub children: usize,
+}
+
+/// The nodes of the search tree.
+///
+/// Unless the states are interned, a node is released once it is neither
+/// open nor an ancestor of an open node, and its slot and the one of its
+/// state are reused for a new node, so the memory of a tree search is
+/// bounded by the open nodes and their ancestors.
+#[derive(Default)]
+pub struct Tree {
+    pub nodes: Vec<Node>,
+    /// The slots of the released nodes, whose content is stale.
+    pub free: Vec<usize>,
+}
+
+impl Tree {
+    /// Stores a node of the state `admitted`, child of `parent`, returning
+    /// its index.
+    pub fn store<T, H>(
+        &mut self,
+        arena: &mut StateArena<T, H>,
+        admitted: Admitted<T>,
+        parent: Option<usize>,
+        cost: f32,
+        depth: usize,
+    ) -> usize {
+        let (index, state) = match admitted {
+            Admitted::Stored(id) => (self.nodes.len(), id),
+            // Released nodes own their states, whose slots can be reused.
+            Admitted::Owned(state) => match self.free.pop() {
+                Some(index) => {
+                    let id = self.nodes[index].state;
+                    arena.replace(id, state);
+                    (index, id)
+                }
+                None => (self.nodes.len(), arena.push(state)),
+            },
+        };
+
+        let node = Node {
+            state,
+            parent,
+            cost,
+            depth,
+            children: 0,
+        };
+        if index == self.nodes.len() {
+            self.nodes.push(node);
+        } else {
+            self.nodes[index] = node;
+        }
+        if let Some(parent) = parent {
+            self.nodes[parent].children += 1;
+        }
+
+        index
+    }
+
+    /// Releases the node `index`, which is not open and has no children,
+    /// along with the ancestors it leaves without children.
+    ///
+    /// The roots are kept, the initial state being reported to observers.
+    pub fn release(&mut self, mut index: usize) {
+        loop {
+            debug_assert_eq!(self.nodes[index].children, 0);
+            let Some(parent) = self.nodes[index].parent else {
+                return;
+            };
+            self.free.push(index);
+
+            self.nodes[parent].children -= 1;
+            if self.nodes[parent].children > 0 {
+                return;
+            }
+            index = parent;
+        }
+    }
+}
+
+/// The open list, holding indices of nodes.
+pub enum Frontier {
+    Fifo(VecDeque<usize>),
+    Lifo(Vec<usize>),
+    Random(Vec<usize>, ThreadRng),
+    Priority(MinPrioriyQueue<usize>, TieBreak),
+}
+
+impl Frontier {
+    pub fn new(kind: FrontierKind, tie_break: TieBreak) -> Self {
+        match kind {
+            FrontierKind::Fifo => Self::Fifo(VecDeque::new()),
+            FrontierKind::Lifo => Self::Lifo(Vec::new()),
+            FrontierKind::Random => Self::Random(Vec::new(), thread_rng()),
+            FrontierKind::Priority => Self::Priority(
+                MinPrioriyQueue::with_insertion_order(tie_break == TieBreak::Lifo),
+                tie_break,
+            ),
+        }
+    }
+
+    pub fn push(&mut self, node: usize, priority: f32, cost: f32) {
+        match self {
+            Self::Fifo(open) => open.push_back(node),
+            Self::Lifo(open) | Self::Random(open, _) => open.push(node),
+            Self::Priority(open, tie_break) => {
+                let tie = match tie_break {
+                    TieBreak::Fifo | TieBreak::Lifo => 0.,
+                    TieBreak::LowestCost => cost,
+                    TieBreak::HighestCost => -cost,
+                };
+                open.enqueue_with_tie(priority, tie, node);
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        match self {
+            Self::Fifo(open) => open.pop_front(),
+            Self::Lifo(open) => open.pop(),
+            Self::Random(open, rng) => {
+                if open.is_empty() {
+                    None
+                } else {
+                    Some(open.swap_remove(rng.gen_range(0..open.len())))
+                }
+            }
+            Self::Priority(open, _) => open.dequeue(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Fifo(open) => open.len(),
+            Self::Lifo(open) | Self::Random(open, _) => open.len(),
+            Self::Priority(open, _) => open.len(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub const fn kind(&self) -> FrontierKind {
+        match self {
+            Self::Fifo(_) => FrontierKind::Fifo,
+            Self::Lifo(_) => FrontierKind::Lifo,
+            Self::Random(..) => FrontierKind::Random,
+            Self::Priority(..) => FrontierKind::Priority,
+        }
+    }
+
+    /// Returns the open nodes along with their priorities, in insertion
+    /// order.
+    #[cfg(feature = "serde")]
+    pub fn entries(&self) -> Vec<(usize, f32)> {
+        match self {
+            Self::Priority(open, _) => open
+                .entries()
+                .into_iter()
+                .map(|(priority, &node)| (node, priority))
+                .collect(),
+            _ => self.iter().map(|node| (node, 0.)).collect(),
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        match self {
+            Self::Fifo(open) => Box::new(open.iter().copied()),
+            Self::Lifo(open) | Self::Random(open, _) => Box::new(open.iter().copied()),
+            Self::Priority(open, _) => Box::new(open.iter().copied()),
+        }
+    }
+}
+
+/// Returns the states from the root to the node `index`.
+pub fn path_to<T: Clone>(nodes: &[Node], states: &[T], index: usize) -> Vec<T> {
+    let mut path = Vec::with_capacity(nodes[index].depth + 1);
+
+    let mut current = Some(index);
+    while let Some(index) = current {
+        path.push(states[nodes[index].state.index()].clone());
+        current = nodes[index].parent;
+    }
+
+    path.reverse();
+    path
+}
+
+/// The state of a search between two expansions.
+pub struct SearchState<T, H> {
+    pub frontier: Frontier,
+    pub visited: Visited<T>,
+    pub arena: StateArena<T, H>,
+    pub tree: Tree,
+    pub stats: SearchStats,
+    /// Why states have been pruned, if some have.
+    pub pruned: Option<Termination>,
+}
+
+/// Creates the state of a search from `sources`, initial states reached with
+/// the given costs, only holding them.
+pub fn start<S, A, H>(
+    space: &S,
+    algorithm: &A,
+    config: &Config,
+    hasher: H,
+    sources: impl IntoIterator<Item = (S::State, f32)>,
+    goals: &[S::State],
+) -> SearchState<S::State, H>
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S> + ?Sized,
+    H: BuildHasher,
+{
+    let mut frontier = Frontier::new(algorithm.frontier(), config.tie_break);
+    let mut visited = Visited::new(config.duplicate_detection);
+    let mut arena = StateArena::with_hasher(hasher);
+    let mut tree = Tree::default();
+
+    // The cheapest of duplicated sources is kept.
+    let mut sources: Vec<_> = sources.into_iter().collect();
+    sources.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    for (source, cost) in sources {
+        let priority = priority(space, algorithm, goals, &source, cost);
+        let Some(admitted) = visited.insert_root(&mut arena, source, cost) else {
+            continue;
+        };
+
+        let index = tree.store(&mut arena, admitted, None, cost, 0);
+        frontier.push(index, priority, cost);
+    }
+
+    SearchState {
+        stats: SearchStats {
+            max_open: frontier.len(),
+            ..SearchStats::default()
+        },
+        frontier,
+        visited,
+        arena,
+        tree,
+        pruned: None,
+    }
+}
+
+/// Returns the priority of `state` reached with `cost`, the lowest towards
+/// any of the goals.
+fn priority<S, A>(space: &S, algorithm: &A, goals: &[S::State], state: &S::State, cost: f32) -> f32
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S> + ?Sized,
+{
+    goals
+        .iter()
+        .map(|goal| algorithm.priority(space, state, goal, cost))
+        .reduce(f32::min)
+        .unwrap_or(cost)
+}
+
+/// Runs a best-first search driven by `algorithm`.
+pub fn search<S, A, O, H>(
+    space: &S,
+    algorithm: &A,
+    observer: &mut O,
+    config: &Config,
+    hasher: H,
+    init: S::State,
+    goals: &[S::State],
+) -> SearchResult<S::State>
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S> + ?Sized,
+    O: Observer<S>,
+    H: BuildHasher,
+{
+    let state = start(space, algorithm, config, hasher, [(init, 0.)], goals);
+    resume(space, algorithm, observer, config, state, goals)
+}
+
+/// Runs a best-first search driven by `algorithm` from `state`.
+pub fn resume<S, A, O, H>(
+    space: &S,
+    algorithm: &A,
+    observer: &mut O,
+    config: &Config,
+    mut state: SearchState<S::State, H>,
+    goals: &[S::State],
+) -> SearchResult<S::State>
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S> + ?Sized,
+    O: Observer<S>,
+    H: BuildHasher,
+{
+    match drive(space, algorithm, observer, config, &mut state, goals) {
+        Ok(index) => SearchResult {
+            path: Some(path_to(&state.tree.nodes, state.arena.states(), index)),
+            cost: Some(state.tree.nodes[index].cost),
+            termination: Termination::Found,
+            stats: state.stats,
+        },
+        Err(termination) => SearchResult {
+            path: None,
+            cost: None,
+            termination,
+            stats: state.stats,
+        },
+    }
+}
+
+/// Expands the states of `state` until one of the goals is reached, returning
+/// the index of its node, or until the search stops otherwise.
+pub fn drive<S, A, O, H>(
+    space: &S,
+    algorithm: &A,
+    observer: &mut O,
+    config: &Config,
+    state: &mut SearchState<S::State, H>,
+    goals: &[S::State],
+) -> Result<usize, Termination>
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S> + ?Sized,
+    O: Observer<S>,
+    H: BuildHasher,
+{
+    let start = Instant::now();
+    let reopens = algorithm.reopens();
+    let limits = &config.limits;
+    let SearchState {
+        frontier,
+        visited,
+        arena,
+        tree,
+        stats,
+        pruned,
+    } = state;
+    let reclaims = visited.reclaims();
+
+    let priority = |state: &S::State, cost| priority(space, algorithm, goals, state, cost);
+    let mut successors = Vec::new();
+
+    let outcome = loop {
+        let Some(index) = frontier.pop() else {
+            break Err(pruned.unwrap_or(Termination::Exhausted));
+        };
+        let node = &tree.nodes[index];
+
+        // A cheaper path to this state has been found since it was opened.
+        if reopens && visited.is_stale(arena, node.state, node.cost) {
+            if reclaims {
+                tree.release(index);
+            }
+            continue;
+        }
+
+        if goals.contains(&arena[node.state]) {
+            break Ok(index);
+        }
+
+        if let Some(termination) = config.interruption(start, stats) {
+            break Err(termination);
+        }
+        if limits.max_depth.is_some_and(|max| node.depth >= max) {
+            *pruned = Some(Termination::DepthLimit);
+            if reclaims {
+                tree.release(index);
+            }
+            continue;
+        }
+
+        let current = node.state;
+        let current_cost = node.cost;
+        let depth = node.depth + 1;
+        stats.expanded += 1;
+
+        // The successors are buffered as the arena cannot be modified while
+        // the current state is borrowed from it.
+        space.for_each_neighbour(&arena[current], &mut |neighbour| {
+            stats.generated += 1;
+
+            let step = algorithm.cost(space, &arena[current], &neighbour);
+            debug_assert!(
+                !config.check_costs || step >= 0.,
+                "Transition costs should be non-negative, see `CostStateSpace::bellman_ford`"
+            );
+            let cost = current_cost + step;
+            if limits.max_cost.is_some_and(|max| cost > max) {
+                *pruned = Some(Termination::CostLimit);
+                return;
+            }
+
+            successors.push((neighbour, cost));
+        });
+
+        // The buffer keeps its capacity for the next expansions.
+        #[allow(clippy::iter_with_drain)]
+        for (neighbour, cost) in successors.drain(..) {
+            let Some(admitted) = visited.admit(arena, &tree.nodes, index, neighbour, cost, reopens)
+            else {
+                continue;
+            };
+
+            let child = tree.store(arena, admitted, Some(index), cost, depth);
+            frontier.push(child, priority(&arena[tree.nodes[child].state], cost), cost);
+        }
+
+        stats.max_open = stats.max_open.max(frontier.len());
+        if reclaims && tree.nodes[index].children == 0 {
+            tree.release(index);
+        }
+
+        observer.on_expand(
+            space,
+            &Progress {
+                init: &arena[tree.nodes[0].state],
+                goal: goals.first(),
+                goals,
+                current: &arena[current],
+                cost: current_cost,
+                depth: depth - 1,
+                stats,
+                nodes: &tree.nodes,
+                free: &tree.free,
+                states: arena.states(),
+                frontier,
+                visited,
+                pruned: *pruned,
+            },
+        );
+    };
+
+    stats.omission_probability = visited.omission_probability();
+    outcome
+}
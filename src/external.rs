@@ -0,0 +1,559 @@
+//! A breadth-first search keeping its layers on disk.
+//!
+//! [`ExternalSearch`] handles state spaces too large for the visited set of
+//! [`StateSpace::breadth_first_search`] to fit in memory. Each layer is
+//! written to a file of fixed-size records, the encodings of its states by
+//! [`StateCodec`], sorted. The
+//! successors of a layer are sorted in runs bounded in memory, merged, and the
+//! states already found in previous layers are removed by merging against
+//! their files, which is known as delayed duplicate detection.
+//!
+//! Every run writes to a subdirectory of its own, so several searches can
+//! share a directory.
+//!
+//! ```
+//! # use state_space::StateSpace;
+//! # use state_space::external::ExternalSearch;
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = (u16, u16);
+//! #     fn neighbours(&self, &(x, y): &(u16, u16)) -> Vec<(u16, u16)> {
+//! #         vec![(x + 1, y), (x, y + 1)]
+//! #     }
+//! # }
+//! let dir = std::env::temp_dir().join(format!("external-search-doctest-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir).unwrap();
+//!
+//! let result = ExternalSearch::new(&Line, &dir).run((0, 0), (2, 1)).unwrap();
+//! assert_eq!(result.cost, Some(3.));
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use crate::codec::{self, BitReader, BitWriter, StateCodec};
+use crate::search::{SearchResult, SearchStats, Termination};
+use crate::StateSpace;
+
+/// A breadth-first search storing its layers in files of a directory.
+#[must_use]
+pub struct ExternalSearch<'a, S: ?Sized> {
+    space: &'a S,
+    dir: PathBuf,
+    size: usize,
+    run_len: usize,
+    window: Option<usize>,
+}
+
+impl<'a, S> ExternalSearch<'a, S>
+where
+    S: StateSpace + ?Sized,
+    S::State: StateCodec,
+{
+    /// Creates a search of `space` writing its files to a subdirectory of
+    /// the existing directory `dir`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the states are not encoded with a fixed number of bits.
+    #[inline]
+    pub fn new(space: &'a S, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            space,
+            dir: dir.into(),
+            size: codec::fixed_bytes::<S::State>()
+                .expect("States should be encoded with a fixed number of bits"),
+            run_len: 1 << 20,
+            window: None,
+        }
+    }
+
+    /// Sets the number of successors sorted in memory before being written
+    /// to disk, 2^20 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `run_len` is zero.
+    #[inline]
+    pub fn run_len(mut self, run_len: usize) -> Self {
+        assert!(run_len > 0, "Runs should hold at least one state");
+        self.run_len = run_len;
+        self
+    }
+
+    /// Only removes the successors found in the last `layers` layers instead
+    /// of every previous one, which is enough for undirected state spaces
+    /// with `layers` set to 2.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `layers` is zero.
+    #[inline]
+    pub fn duplicate_window(mut self, layers: usize) -> Self {
+        assert!(
+            layers > 0,
+            "Duplicates should be detected in a layer at least"
+        );
+        self.window = Some(layers);
+        self
+    }
+
+    /// Runs the search from `init` to `goal`.
+    ///
+    /// The path is rebuilt by scanning the layers backwards for a parent of
+    /// each of its states. The subdirectory of the files is removed before
+    /// returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files cannot be written or read.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn run(&self, init: S::State, goal: S::State) -> io::Result<SearchResult<S::State>> {
+        let mut layers = Layers::new(&self.dir)?;
+        let result = self.search(&mut layers, &init, Some(&goal), &mut |_, _| {});
+
+        let result = result.and_then(|(depth, stats)| match depth {
+            Some(depth) => Ok(SearchResult {
+                path: Some(self.path_to(&layers, goal, depth)?),
+                #[allow(clippy::cast_precision_loss)]
+                cost: Some(depth as f32),
+                termination: Termination::Found,
+                stats,
+            }),
+            None => Ok(SearchResult {
+                path: None,
+                cost: None,
+                termination: Termination::Exhausted,
+                stats,
+            }),
+        });
+
+        layers.remove()?;
+        result
+    }
+
+    /// Reaches every state from `init`, calling `f` on each of them along
+    /// with its depth as they are read back from disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files cannot be written or read.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn explore<F>(&self, init: S::State, mut f: F) -> io::Result<SearchStats>
+    where
+        F: FnMut(S::State, usize),
+    {
+        let mut layers = Layers::new(&self.dir)?;
+        let result = self.search(&mut layers, &init, None, &mut f);
+
+        layers.remove()?;
+        result.map(|(_, stats)| stats)
+    }
+
+    /// Expands the layers until `goal` is reached, returning its depth.
+    fn search(
+        &self,
+        layers: &mut Layers,
+        init: &S::State,
+        goal: Option<&S::State>,
+        f: &mut dyn FnMut(S::State, usize),
+    ) -> io::Result<(Option<usize>, SearchStats)> {
+        let mut stats = SearchStats {
+            max_open: 1,
+            ..SearchStats::default()
+        };
+
+        let mut writer = layers.create(0)?;
+        writer.write_all(&init.to_bytes())?;
+        writer.flush()?;
+
+        for depth in 0_usize.. {
+            let first = self
+                .window
+                .map_or(0, |window| (depth + 1).saturating_sub(window));
+            let previous: Vec<_> = (first..=depth).map(|layer| layers.path(layer)).collect();
+            let next = layers.path(depth + 1);
+
+            let mut reader = RecordReader::open(&layers.path(depth), self.size)?;
+            let mut successors = Successors::new(layers, depth + 1, self.size, self.run_len);
+            let mut len = 0;
+
+            while let Some(record) = reader.next_record()? {
+                let current = decode(&record)?;
+                if goal == Some(&current) {
+                    return Ok((Some(depth), stats));
+                }
+
+                len += 1;
+                stats.expanded += 1;
+                self.space.for_each_neighbour(&current, &mut |neighbour| {
+                    stats.generated += 1;
+                    successors.push(&neighbour);
+                });
+                successors.flush_if_full()?;
+
+                f(current, depth);
+            }
+
+            stats.max_open = stats.max_open.max(len);
+
+            if successors.merge(&previous, &next)? == 0 {
+                break;
+            }
+        }
+
+        Ok((None, stats))
+    }
+
+    /// Returns the path to `goal` found at `depth`.
+    fn path_to(&self, layers: &Layers, goal: S::State, depth: usize) -> io::Result<Vec<S::State>> {
+        let mut path = vec![goal];
+
+        for layer in (0..depth).rev() {
+            let child = &path[path.len() - 1];
+            let mut reader = RecordReader::open(&layers.path(layer), self.size)?;
+
+            let parent = loop {
+                let record = reader.next_record()?.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "missing parent in layer")
+                })?;
+                let state = decode(&record)?;
+
+                let mut is_parent = false;
+                self.space.for_each_neighbour(&state, &mut |neighbour| {
+                    is_parent |= neighbour == *child;
+                });
+                if is_parent {
+                    break state;
+                }
+            };
+            path.push(parent);
+        }
+
+        path.reverse();
+        Ok(path)
+    }
+}
+
+fn decode<T: StateCodec>(record: &[u8]) -> io::Result<T> {
+    T::decode(&mut BitReader::new(record))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid state record"))
+}
+
+/// The number of subdirectories created by this process, to name them.
+static SEARCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// The files of the layers and sorted runs of a search, in a subdirectory of
+/// their own.
+struct Layers {
+    dir: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+impl Layers {
+    /// Creates a subdirectory of `parent` that no other search uses.
+    fn new(parent: &Path) -> io::Result<Self> {
+        loop {
+            let search = SEARCHES.fetch_add(1, AtomicOrdering::Relaxed);
+            let dir = parent.join(format!("search-{}-{search}", std::process::id()));
+            match fs::create_dir(&dir) {
+                Ok(()) => {
+                    return Ok(Self {
+                        dir,
+                        files: Vec::new(),
+                    })
+                }
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn path(&self, depth: usize) -> PathBuf {
+        self.dir.join(format!("layer-{depth}"))
+    }
+
+    fn run_path(&self, depth: usize, run: usize) -> PathBuf {
+        self.dir.join(format!("layer-{depth}-run-{run}"))
+    }
+
+    fn create(&mut self, depth: usize) -> io::Result<BufWriter<File>> {
+        self.create_file(self.path(depth))
+    }
+
+    fn create_file(&mut self, path: PathBuf) -> io::Result<BufWriter<File>> {
+        let file = File::create(&path)?;
+        self.files.push(path);
+        Ok(BufWriter::new(file))
+    }
+
+    /// Removes the files created and their subdirectory.
+    fn remove(self) -> io::Result<()> {
+        for file in self.files {
+            match fs::remove_file(file) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+        }
+        fs::remove_dir(self.dir)
+    }
+}
+
+/// The successors of a layer, sorted in runs written to disk.
+struct Successors<'a> {
+    layers: &'a mut Layers,
+    depth: usize,
+    size: usize,
+    run_len: usize,
+    buffer: Vec<u8>,
+    runs: Vec<PathBuf>,
+}
+
+impl<'a> Successors<'a> {
+    const fn new(layers: &'a mut Layers, depth: usize, size: usize, run_len: usize) -> Self {
+        Self {
+            layers,
+            depth,
+            size,
+            run_len,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    fn push<T: StateCodec>(&mut self, state: &T) {
+        let mut writer = BitWriter::new();
+        state.encode(&mut writer);
+        self.buffer.extend(writer.into_bytes());
+    }
+
+    fn flush_if_full(&mut self) -> io::Result<()> {
+        if self.buffer.len() >= self.run_len * self.size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered successors sorted and without duplicates.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let mut records: Vec<_> = self.buffer.chunks_exact(self.size).collect();
+        records.sort_unstable();
+        records.dedup();
+
+        let path = self.layers.run_path(self.depth, self.runs.len());
+        let mut writer = self.layers.create_file(path.clone())?;
+        for record in records {
+            writer.write_all(record)?;
+        }
+        writer.flush()?;
+
+        self.buffer.clear();
+        self.runs.push(path);
+        Ok(())
+    }
+
+    /// Merges the runs into the layer at `path`, leaving out the states of
+    /// the `previous` layers, and returns the number of states written.
+    fn merge(mut self, previous: &[PathBuf], path: &Path) -> io::Result<usize> {
+        self.flush()?;
+
+        let mut writer = self.layers.create_file(path.to_path_buf())?;
+        if self.runs.is_empty() {
+            writer.flush()?;
+            return Ok(0);
+        }
+
+        let mut runs = self
+            .runs
+            .iter()
+            .map(|run| RecordReader::open(run, self.size))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut previous = previous
+            .iter()
+            .map(|layer| SortedCursor::open(layer, self.size))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut heap = BinaryHeap::new();
+        for (index, run) in runs.iter_mut().enumerate() {
+            if let Some(record) = run.next_record()? {
+                heap.push(Reverse((record, index)));
+            }
+        }
+
+        let mut written = 0;
+        let mut last: Option<Vec<u8>> = None;
+        while let Some(Reverse((record, index))) = heap.pop() {
+            if let Some(next) = runs[index].next_record()? {
+                heap.push(Reverse((next, index)));
+            }
+
+            if last.as_ref() == Some(&record) {
+                continue;
+            }
+
+            let mut duplicate = false;
+            for cursor in &mut previous {
+                duplicate |= cursor.contains(&record)?;
+            }
+            if !duplicate {
+                writer.write_all(&record)?;
+                written += 1;
+            }
+            last = Some(record);
+        }
+        writer.flush()?;
+
+        for run in self.runs {
+            fs::remove_file(run)?;
+        }
+        Ok(written)
+    }
+}
+
+/// A reader of the fixed-size records of a file.
+struct RecordReader {
+    reader: BufReader<File>,
+    size: usize,
+}
+
+impl RecordReader {
+    fn open(path: &Path, size: usize) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+            size,
+        })
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut record = vec![0; self.size];
+        match self.reader.read_exact(&mut record) {
+            Ok(()) => Ok(Some(record)),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// A reader of a sorted file answering membership queries for increasing
+/// records.
+struct SortedCursor {
+    reader: RecordReader,
+    current: Option<Vec<u8>>,
+}
+
+impl SortedCursor {
+    fn open(path: &Path, size: usize) -> io::Result<Self> {
+        let mut reader = RecordReader::open(path, size)?;
+        let current = reader.next_record()?;
+        Ok(Self { reader, current })
+    }
+
+    /// Returns whether the file holds `record`, which must not be lower than
+    /// the records previously queried.
+    fn contains(&mut self, record: &[u8]) -> io::Result<bool> {
+        while let Some(current) = &self.current {
+            match current.as_slice().cmp(record) {
+                Ordering::Less => self.current = self.reader.next_record()?,
+                Ordering::Equal => return Ok(true),
+                Ordering::Greater => return Ok(false),
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("state-space-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn matches_breadth_first_search() {
+        let dir = temp_dir("external-bfs");
+        let grid = Grid::from_rows(&[
+            "..........",
+            ".XXXXXXXX.",
+            ".X......X.",
+            ".X.XXXX.X.",
+            ".X.X..X.X.",
+            ".X.X.XX.X.",
+            "...X......",
+        ]);
+
+        // Tiny runs to exercise the merge of several of them.
+        let result = ExternalSearch::new(&grid, &dir)
+            .run_len(2)
+            .run((0, 0), (4, 4))
+            .unwrap();
+        let expected = grid.search().run((0, 0), (4, 4));
+
+        assert_eq!(result.cost, expected.cost);
+        let path = result.path.unwrap();
+        assert!(path
+            .windows(2)
+            .all(|w| grid.neighbours(&w[0]).contains(&w[1])));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn explore_streams_every_state_once() {
+        let dir = temp_dir("external-explore");
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+
+        let mut reached = Vec::new();
+        let stats = ExternalSearch::new(&grid, &dir)
+            .duplicate_window(2)
+            .run_len(3)
+            .explore((0, 0), |state, depth| reached.push((state, depth)))
+            .unwrap();
+
+        let total = reached.len();
+        reached.sort_unstable();
+        reached.dedup_by_key(|(state, _)| *state);
+
+        assert_eq!(total, 12);
+        assert_eq!(reached.len(), 12);
+        assert_eq!(stats.expanded, 12);
+        assert!(reached.contains(&((3, 3), 6)));
+    }
+
+    #[test]
+    fn searches_share_a_directory() {
+        let dir = temp_dir("external-shared");
+        let grid = Grid::open(6, 6);
+
+        let costs: Vec<_> = std::thread::scope(|scope| {
+            // Collected to spawn every search before joining any.
+            #[allow(clippy::needless_collect)]
+            let searches: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        ExternalSearch::new(&grid, &dir)
+                            .run_len(4)
+                            .run((0, 0), (5, 5))
+                            .unwrap()
+                            .cost
+                    })
+                })
+                .collect();
+            searches.into_iter().map(|s| s.join().unwrap()).collect()
+        });
+
+        assert!(costs.iter().all(|&cost| cost == Some(10.)));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
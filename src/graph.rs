@@ -0,0 +1,293 @@
+//! A state space given by the list of its transitions.
+//!
+//! Implicit state spaces generate neighbours on the fly, while an
+//! [`ExplicitGraph`] stores its transitions, for instance loaded from a file
+//! or a database. They are stored in compressed sparse rows, the successors
+//! and predecessors of each state being contiguous.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # use state_space::graph::ExplicitGraph;
+//! let graph: ExplicitGraph<_, u16> = ExplicitGraph::from_edges(vec![
+//!     ("home", "station", 5),
+//!     ("station", "office", 20),
+//!     ("home", "office", 40),
+//! ]);
+//!
+//! assert_eq!(graph.dijkstra("home", "office"), vec!["home", "station", "office"]);
+//! assert_eq!(graph.predecessors(&"office").count(), 2);
+//! ```
+use std::collections::hash_map::RandomState;
+use std::hash::Hash;
+use std::iter::FromIterator;
+
+use crate::arena::{StateArena, StateId};
+use crate::{CostStateSpace, StateSpace};
+
+/// A state space of states `S` whose transitions are stored along with their
+/// costs `C`.
+///
+/// Costs are converted to `f32` with [`Into`], so they can be `f32`, `u16` or
+/// `u8` for instance. Parallel transitions are kept, the cheapest one giving
+/// the cost between two states.
+#[derive(Clone)]
+pub struct ExplicitGraph<S, C = f32, H = RandomState> {
+    arena: StateArena<S, H>,
+    /// The start of the successors of each state in `targets`, followed by
+    /// the number of transitions.
+    offsets: Vec<usize>,
+    /// The successors of the states, sorted by identifier then by cost.
+    targets: Vec<StateId>,
+    costs: Vec<C>,
+    /// The start of the predecessors of each state in `incoming`, followed by
+    /// the number of transitions.
+    reverse_offsets: Vec<usize>,
+    /// The indices of the transitions leading to each state.
+    incoming: Vec<usize>,
+    /// The states from which the transitions start.
+    sources: Vec<StateId>,
+}
+
+impl<S, C> ExplicitGraph<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Into<f32>,
+{
+    /// Builds the graph from its transitions, given as triples of a state,
+    /// one of its successors and the cost of the transition.
+    pub fn from_edges(edges: impl IntoIterator<Item = (S, S, C)>) -> Self {
+        let mut arena = StateArena::new();
+        let edges: Vec<_> = edges
+            .into_iter()
+            .map(|(from, to, cost)| (arena.intern(from).0, arena.intern(to).0, cost))
+            .collect();
+
+        Self::build(arena, edges)
+    }
+
+    /// Builds the graph from the successors of each state along with the
+    /// costs of the transitions, like a `HashMap<S, Vec<(S, C)>>`.
+    pub fn from_adjacency<I>(adjacency: impl IntoIterator<Item = (S, I)>) -> Self
+    where
+        I: IntoIterator<Item = (S, C)>,
+    {
+        let mut arena = StateArena::new();
+        let mut edges = Vec::new();
+        for (from, successors) in adjacency {
+            let (from, _) = arena.intern(from);
+            for (to, cost) in successors {
+                edges.push((from, arena.intern(to).0, cost));
+            }
+        }
+
+        Self::build(arena, edges)
+    }
+
+    fn build(arena: StateArena<S>, mut edges: Vec<(StateId, StateId, C)>) -> Self {
+        let n = arena.len();
+        edges.sort_by(|(from, to, cost), (other_from, other_to, other_cost)| {
+            (from, to)
+                .cmp(&(other_from, other_to))
+                .then_with(|| (*cost).into().total_cmp(&(*other_cost).into()))
+        });
+
+        let mut offsets = vec![0; n + 1];
+        let mut reverse_offsets = vec![0; n + 1];
+        for &(from, to, _) in &edges {
+            offsets[from.index() + 1] += 1;
+            reverse_offsets[to.index() + 1] += 1;
+        }
+        for i in 0..n {
+            offsets[i + 1] += offsets[i];
+            reverse_offsets[i + 1] += reverse_offsets[i];
+        }
+
+        let mut incoming = vec![0; edges.len()];
+        let mut filled = reverse_offsets.clone();
+        for (index, &(_, to, _)) in edges.iter().enumerate() {
+            incoming[filled[to.index()]] = index;
+            filled[to.index()] += 1;
+        }
+
+        Self {
+            arena,
+            offsets,
+            targets: edges.iter().map(|&(_, to, _)| to).collect(),
+            costs: edges.iter().map(|&(_, _, cost)| cost).collect(),
+            reverse_offsets,
+            incoming,
+            sources: edges.iter().map(|&(from, _, _)| from).collect(),
+        }
+    }
+}
+
+impl<S, C, H> ExplicitGraph<S, C, H> {
+    /// Returns the number of states.
+    #[inline]
+    #[must_use]
+    pub fn state_count(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns the number of transitions.
+    #[inline]
+    #[must_use]
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Returns the states, indexed by their [`StateId`].
+    #[inline]
+    #[must_use]
+    pub const fn arena(&self) -> &StateArena<S, H> {
+        &self.arena
+    }
+}
+
+impl<S, C, H> ExplicitGraph<S, C, H>
+where
+    S: Eq + Hash,
+    C: Copy,
+    H: std::hash::BuildHasher,
+{
+    /// Returns the successors of `state` along with the costs of the
+    /// transitions, none if it is not in the graph.
+    pub fn successors(&self, state: &S) -> impl Iterator<Item = (&S, C)> {
+        let edges = self.arena.get(state).map_or(0..0, |id| {
+            self.offsets[id.index()]..self.offsets[id.index() + 1]
+        });
+
+        edges.map(move |edge| (&self.arena[self.targets[edge]], self.costs[edge]))
+    }
+
+    /// Returns the predecessors of `state` along with the costs of the
+    /// transitions, none if it is not in the graph.
+    pub fn predecessors(&self, state: &S) -> impl Iterator<Item = (&S, C)> {
+        let edges = self.arena.get(state).map_or(0..0, |id| {
+            self.reverse_offsets[id.index()]..self.reverse_offsets[id.index() + 1]
+        });
+
+        edges.map(move |i| {
+            let edge = self.incoming[i];
+            (&self.arena[self.sources[edge]], self.costs[edge])
+        })
+    }
+}
+
+impl<S, C> FromIterator<(S, S, C)> for ExplicitGraph<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Into<f32>,
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (S, S, C)>>(edges: I) -> Self {
+        Self::from_edges(edges)
+    }
+}
+
+impl<S, C> StateSpace for ExplicitGraph<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy,
+{
+    type State = S;
+
+    fn for_each_neighbour(&self, state: &S, f: &mut dyn FnMut(S)) {
+        let Some(id) = self.arena.get(state) else {
+            return;
+        };
+
+        for &to in &self.targets[self.offsets[id.index()]..self.offsets[id.index() + 1]] {
+            f(self.arena[to].clone());
+        }
+    }
+}
+
+impl<S, C> CostStateSpace for ExplicitGraph<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Into<f32>,
+{
+    /// Returns the cost of the cheapest transition from `current` to `next`,
+    /// infinite if there is none.
+    fn cost(&self, current: &S, next: &S) -> f32 {
+        let (Some(from), Some(to)) = (self.arena.get(current), self.arena.get(next)) else {
+            return f32::INFINITY;
+        };
+
+        let start = self.offsets[from.index()];
+        let row = &self.targets[start..self.offsets[from.index() + 1]];
+        let i = row.partition_point(|&target| target < to);
+
+        if row.get(i) == Some(&to) {
+            self.costs[start + i].into()
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn graph() -> ExplicitGraph<char, u8> {
+        ExplicitGraph::from_edges([
+            ('a', 'b', 4),
+            ('a', 'c', 1),
+            ('c', 'b', 2),
+            ('b', 'd', 1),
+            ('c', 'd', 5),
+            ('a', 'b', 2),
+            ('e', 'a', 1),
+        ])
+    }
+
+    #[test]
+    fn compressed_rows() {
+        let graph = graph();
+
+        assert_eq!(graph.state_count(), 5);
+        assert_eq!(graph.edge_count(), 7);
+
+        let mut successors: Vec<_> = graph.successors(&'a').collect();
+        successors.sort_unstable();
+        assert_eq!(successors, vec![(&'b', 2), (&'b', 4), (&'c', 1)]);
+
+        let mut predecessors: Vec<_> = graph.predecessors(&'b').collect();
+        predecessors.sort_unstable();
+        assert_eq!(predecessors, vec![(&'a', 2), (&'a', 4), (&'c', 2)]);
+
+        assert_eq!(graph.successors(&'d').count(), 0);
+        assert_eq!(graph.predecessors(&'z').count(), 0);
+    }
+
+    #[test]
+    fn searches_run_on_it() {
+        let graph = graph();
+
+        assert!((graph.cost(&'a', &'b') - 2.).abs() < f32::EPSILON);
+        assert!(graph.cost(&'b', &'a').is_infinite());
+        assert_eq!(graph.dijkstra('a', 'd'), vec!['a', 'b', 'd']);
+        assert_eq!(
+            graph.breadth_first_search('e', 'd'),
+            vec!['e', 'a', 'b', 'd']
+        );
+        assert_eq!(
+            graph.search().run('d', 'a').termination,
+            crate::search::Termination::Exhausted
+        );
+    }
+
+    #[test]
+    fn from_adjacency() {
+        let adjacency = HashMap::from([(1, vec![(2, 1.5_f32), (3, 1.)]), (3, vec![(2, 0.25)])]);
+        let graph = ExplicitGraph::from_adjacency(adjacency);
+
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.dijkstra(1, 2), vec![1, 3, 2]);
+        assert_eq!(graph.predecessors(&2).count(), 2);
+    }
+}
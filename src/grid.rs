@@ -0,0 +1,650 @@
+//! Two-dimensional grids of cells, the most common state spaces of
+//! pathfinding.
+//!
+//! A [`Grid`] stores a cell of terrain `T` per position, the [`Terrain`]
+//! telling whether a cell can be entered and at what cost. Moves go to the 4
+//! orthogonally adjacent cells or to the 8 surrounding ones, moving
+//! diagonally costing √2 times as much as moving orthogonally.
+//!
+//! ```
+//! # use state_space::grid::{CellState, Connectivity, CornerCutting, Grid};
+//! # use state_space::HeuristicStateSpace;
+//! let maze: Grid<CellState> = "\
+//! X.....
+//! X.XXX.
+//! ......"
+//!     .parse()
+//!     .unwrap();
+//!
+//! let path = maze.a_star((1, 0), (1, 2));
+//! assert_eq!(path, vec![(1, 0), (1, 1), (1, 2)]);
+//!
+//! let maze = maze.with_connectivity(Connectivity::Eight);
+//! assert_eq!(maze.a_star((0, 2), (5, 0)).len(), 8);
+//!
+//! let maze = maze.with_corner_cutting(CornerCutting::Allowed);
+//! assert_eq!(maze.a_star((0, 2), (5, 0)).len(), 6);
+//! ```
+use std::error::Error;
+use std::fmt;
+use std::ops::Index;
+use std::str::FromStr;
+
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// A position in a grid, as its column and row.
+pub type Position = (usize, usize);
+
+/// The terrain of a cell of a [`Grid`].
+pub trait Terrain {
+    /// Returns the cost of entering the cell by an orthogonal move, `None`
+    /// if it cannot be entered.
+    ///
+    /// Costs should be non-negative.
+    fn cost(&self) -> Option<f32>;
+}
+
+/// The cells of a maze, either walls or ground costing 1 to enter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CellState {
+    /// A cell which cannot be entered.
+    Wall,
+    /// A cell which can be entered.
+    Ground,
+}
+
+impl Terrain for CellState {
+    #[inline]
+    fn cost(&self) -> Option<f32> {
+        match self {
+            Self::Wall => None,
+            Self::Ground => Some(1.),
+        }
+    }
+}
+
+/// A cost, infinite costs being impassable.
+impl Terrain for f32 {
+    #[inline]
+    fn cost(&self) -> Option<f32> {
+        Some(*self).filter(|cost| cost.is_finite())
+    }
+}
+
+/// A cost, `0` being impassable.
+impl Terrain for u8 {
+    #[inline]
+    fn cost(&self) -> Option<f32> {
+        Some(f32::from(*self)).filter(|&cost| cost > 0.)
+    }
+}
+
+impl Terrain for Option<f32> {
+    #[inline]
+    fn cost(&self) -> Option<f32> {
+        *self
+    }
+}
+
+/// The cells adjacent to a cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// The cells above, to the right, below and to the left.
+    Four,
+    /// The orthogonally and diagonally adjacent cells.
+    Eight,
+}
+
+/// Whether diagonal moves can cut the corners of impassable cells.
+///
+/// A diagonal move goes past two cells orthogonally adjacent to both the
+/// cell left and the cell entered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CornerCutting {
+    /// Diagonal moves are allowed whatever the cells they go past.
+    Allowed,
+    /// Diagonal moves cannot squeeze between two impassable cells.
+    NoSqueezing,
+    /// Diagonal moves require both cells they go past to be passable.
+    Forbidden,
+}
+
+/// A distance between positions, used as the heuristic of a [`Grid`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Distance {
+    /// The sum of the horizontal and vertical distances, the length of the
+    /// shortest paths with 4-connectivity, and overestimating it with
+    /// 8-connectivity.
+    Manhattan,
+    /// The length of the shortest paths with 8-connectivity, diagonal moves
+    /// having length √2.
+    Octile,
+    /// The maximum of the horizontal and vertical distances, the number of
+    /// moves of the shortest paths with 8-connectivity.
+    Chebyshev,
+    /// The straight-line distance.
+    Euclidean,
+}
+
+impl Distance {
+    /// Returns the distance between `from` and `to`.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn between(self, from: Position, to: Position) -> f32 {
+        let dx = from.0.abs_diff(to.0) as f32;
+        let dy = from.1.abs_diff(to.1) as f32;
+
+        match self {
+            Self::Manhattan => dx + dy,
+            Self::Octile => (std::f32::consts::SQRT_2 - 1.).mul_add(dx.min(dy), dx.max(dy)),
+            Self::Chebyshev => dx.max(dy),
+            Self::Euclidean => dx.hypot(dy),
+        }
+    }
+}
+
+/// The offsets of the adjacent cells, the orthogonal ones first.
+const OFFSETS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// A grid of cells of terrain `T`, whose states are the positions of the
+/// passable cells.
+///
+/// Entering a cell costs its [`Terrain::cost`], times √2 for diagonal moves.
+/// The heuristic is a [`Distance`] times the cheapest cost of the cells,
+/// the Manhattan distance by default with 4-connectivity and the octile
+/// distance with 8-connectivity, both admissible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    connectivity: Connectivity,
+    corner_cutting: CornerCutting,
+    distance: Option<Distance>,
+    /// A lower bound on the cost of the passable cells.
+    min_cost: f32,
+}
+
+impl<T: Terrain> Grid<T> {
+    /// Creates a 4-connected grid of `width` columns and `height` rows from
+    /// its cells, row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `width * height` cells.
+    #[must_use]
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "A grid should have `width * height` cells"
+        );
+
+        let min_cost = cells
+            .iter()
+            .filter_map(Terrain::cost)
+            .fold(f32::INFINITY, f32::min);
+
+        Self {
+            width,
+            height,
+            cells,
+            connectivity: Connectivity::Four,
+            corner_cutting: CornerCutting::Forbidden,
+            distance: None,
+            min_cost,
+        }
+    }
+
+    /// Creates a grid of `width` columns and `height` rows filled with
+    /// `cell`.
+    #[must_use]
+    pub fn filled(width: usize, height: usize, cell: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![cell; width * height])
+    }
+
+    /// Parses a grid from rows of characters separated by newlines, `cell`
+    /// returning the terrain of each character, or `None` if it is invalid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no rows, if rows have different lengths
+    /// or if a character is invalid.
+    pub fn parse(s: &str, mut cell: impl FnMut(char) -> Option<T>) -> Result<Self, ParseGridError> {
+        let rows: Vec<_> = s.lines().collect();
+        let width = rows.first().ok_or(ParseGridError::Empty)?.chars().count();
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let length = row.chars().count();
+            if length != width {
+                return Err(ParseGridError::Ragged { row: y, length });
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                cells.push(cell(c).ok_or(ParseGridError::InvalidCell {
+                    position: (x, y),
+                    cell: c,
+                })?);
+            }
+        }
+
+        Ok(Self::new(width, rows.len(), cells))
+    }
+
+    /// Replaces the cell at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of the grid.
+    pub fn set(&mut self, position: Position, cell: T) {
+        assert!(
+            self.contains(position),
+            "Position {:?} should be in the grid",
+            position
+        );
+
+        if let Some(cost) = cell.cost() {
+            self.min_cost = self.min_cost.min(cost);
+        }
+        self.cells[position.0 + position.1 * self.width] = cell;
+    }
+
+    /// Returns whether the cell at `position` is in the grid and can be
+    /// entered.
+    #[inline]
+    #[must_use]
+    pub fn is_passable(&self, position: Position) -> bool {
+        self.get(position).and_then(Terrain::cost).is_some()
+    }
+
+    /// Returns a drawing of the grid, the impassable cells being `X` and the
+    /// passable ones spaces, the positions of `path` being `o` apart from its
+    /// first one `S` and its last one `E`.
+    #[inline]
+    #[must_use]
+    pub fn render_path(&self, path: &[Position]) -> String {
+        self.render_search(path, &[])
+    }
+
+    /// Returns a drawing of the grid as [`Grid::render_path`], the positions
+    /// of `open` not on the path being `#`.
+    #[must_use]
+    pub fn render_search(&self, path: &[Position], open: &[Position]) -> String {
+        let mut drawing = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                drawing.push(match (x, y) {
+                    position if !self.is_passable(position) => 'X',
+                    position if path.first() == Some(&position) => 'S',
+                    position if path.last() == Some(&position) => 'E',
+                    position if path.contains(&position) => 'o',
+                    position if open.contains(&position) => '#',
+                    _ => ' ',
+                });
+            }
+            drawing.push('\n');
+        }
+        drawing.pop();
+
+        drawing
+    }
+}
+
+impl<T> Grid<T> {
+    /// Sets the cells adjacent to a cell.
+    #[inline]
+    #[must_use]
+    pub const fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    /// Sets whether diagonal moves can cut corners, forbidden by default.
+    #[inline]
+    #[must_use]
+    pub const fn with_corner_cutting(mut self, corner_cutting: CornerCutting) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    /// Sets the distance used as heuristic.
+    #[inline]
+    #[must_use]
+    pub const fn with_heuristic(mut self, distance: Distance) -> Self {
+        self.distance = Some(distance);
+        self
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether `position` is in the grid.
+    #[inline]
+    #[must_use]
+    pub const fn contains(&self, (x, y): Position) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Returns the cell at `position`, if it is in the grid.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, position: Position) -> Option<&T> {
+        if self.contains(position) {
+            self.cells.get(position.0 + position.1 * self.width)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `position` moved by `(dx, dy)`, if it is still in the grid.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self, (x, y): Position, (dx, dy): (isize, isize)) -> Option<Position> {
+        let position = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        Some(position).filter(|&position| self.contains(position))
+    }
+
+    /// Returns the distance used as heuristic.
+    #[inline]
+    #[must_use]
+    pub fn distance(&self) -> Distance {
+        self.distance.unwrap_or(match self.connectivity {
+            Connectivity::Four => Distance::Manhattan,
+            Connectivity::Eight => Distance::Octile,
+        })
+    }
+
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self.connectivity {
+            Connectivity::Four => &OFFSETS[..4],
+            Connectivity::Eight => &OFFSETS,
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    /// Returns the cell at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of the grid.
+    #[inline]
+    fn index(&self, position: Position) -> &T {
+        self.get(position).expect("Position should be in the grid")
+    }
+}
+
+impl<T: Terrain> StateSpace for Grid<T> {
+    type State = Position;
+
+    fn for_each_neighbour(&self, &(x, y): &Position, f: &mut dyn FnMut(Position)) {
+        for &(dx, dy) in self.offsets() {
+            let Some(next) = self.offset((x, y), (dx, dy)) else {
+                continue;
+            };
+            if !self.is_passable(next) {
+                continue;
+            }
+
+            if dx != 0 && dy != 0 {
+                let passed = [self.is_passable((next.0, y)), self.is_passable((x, next.1))];
+                let allowed = match self.corner_cutting {
+                    CornerCutting::Allowed => true,
+                    CornerCutting::NoSqueezing => passed[0] || passed[1],
+                    CornerCutting::Forbidden => passed[0] && passed[1],
+                };
+                if !allowed {
+                    continue;
+                }
+            }
+
+            f(next);
+        }
+    }
+}
+
+impl<T: Terrain> CostStateSpace for Grid<T> {
+    /// Returns the cost of entering `next`, times √2 if the move is
+    /// diagonal, infinite if it cannot be entered.
+    fn cost(&self, current: &Position, next: &Position) -> f32 {
+        let Some(cost) = self.get(*next).and_then(Terrain::cost) else {
+            return f32::INFINITY;
+        };
+
+        if current.0 != next.0 && current.1 != next.1 {
+            cost * std::f32::consts::SQRT_2
+        } else {
+            cost
+        }
+    }
+}
+
+impl<T: Terrain> HeuristicStateSpace for Grid<T> {
+    #[inline]
+    fn heuristic(&self, state: &Position, goal: &Position) -> f32 {
+        if self.min_cost.is_finite() {
+            self.distance().between(*state, *goal) * self.min_cost
+        } else {
+            0.
+        }
+    }
+}
+
+/// Parses the ASCII maze format, `X` being walls and any other character
+/// ground.
+impl FromStr for Grid<CellState> {
+    type Err = ParseGridError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, |c| {
+            Some(if c == 'X' {
+                CellState::Wall
+            } else {
+                CellState::Ground
+            })
+        })
+    }
+}
+
+/// Formats the grid in the ASCII maze format.
+impl fmt::Display for Grid<CellState> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_path(&[]))
+    }
+}
+
+/// The error returned when parsing a [`Grid`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseGridError {
+    /// There are no rows.
+    Empty,
+    /// A row does not have the length of the first one.
+    Ragged {
+        /// The index of the row.
+        row: usize,
+        /// The number of characters of the row.
+        length: usize,
+    },
+    /// A character is not a valid cell.
+    InvalidCell {
+        /// The position of the character.
+        position: Position,
+        /// The character.
+        cell: char,
+    },
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("empty grid"),
+            Self::Ragged { row, length } => {
+                write!(f, "row {row} has a different length ({length})")
+            }
+            Self::InvalidCell { position, cell } => {
+                write!(f, "invalid cell `{cell}` at {position:?}")
+            }
+        }
+    }
+}
+
+impl Error for ParseGridError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maze(rows: &str) -> Grid<CellState> {
+        rows.parse().unwrap()
+    }
+
+    #[test]
+    fn neighbours() {
+        let open = Grid::filled(3, 3, CellState::Ground);
+        assert_eq!(
+            open.neighbours(&(1, 1)),
+            vec![(1, 0), (2, 1), (1, 2), (0, 1)]
+        );
+        assert_eq!(open.neighbours(&(0, 0)), vec![(1, 0), (0, 1)]);
+
+        let corner = maze("XXX\nX..\nX..");
+        assert_eq!(corner.neighbours(&(1, 1)), vec![(2, 1), (1, 2)]);
+
+        let open = open.with_connectivity(Connectivity::Eight);
+        assert_eq!(open.neighbours(&(1, 1)).len(), 8);
+        assert_eq!(open.neighbours(&(2, 2)), vec![(2, 1), (1, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn corner_cutting() {
+        let grid = maze(".X\nX.").with_connectivity(Connectivity::Eight);
+        let squeeze = |rule| grid.clone().with_corner_cutting(rule).neighbours(&(0, 0));
+
+        assert_eq!(squeeze(CornerCutting::Allowed), vec![(1, 1)]);
+        assert_eq!(squeeze(CornerCutting::NoSqueezing), vec![]);
+        assert_eq!(squeeze(CornerCutting::Forbidden), vec![]);
+
+        let grid = maze("..\nX.").with_connectivity(Connectivity::Eight);
+        let cut = |rule| grid.clone().with_corner_cutting(rule).neighbours(&(0, 0));
+
+        assert_eq!(cut(CornerCutting::NoSqueezing), vec![(1, 0), (1, 1)]);
+        assert_eq!(cut(CornerCutting::Forbidden), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn terrain_costs() {
+        // The middle row is a swamp, cheaper to go around with 4-connectivity.
+        let mut grid = Grid::filled(3, 3, 1_u8);
+        grid.set((1, 1), 9);
+        grid.set((0, 1), 0);
+
+        assert!(!grid.is_passable((0, 1)));
+        assert!((grid.cost(&(2, 0), &(2, 1)) - 1.).abs() < 1e-6);
+        assert!(grid.cost(&(0, 0), &(0, 1)).is_infinite());
+
+        let path = grid.a_star((0, 0), (0, 2));
+        assert_eq!(
+            path,
+            vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2)]
+        );
+
+        let diagonal = grid.with_connectivity(Connectivity::Eight);
+        let path = diagonal.a_star((0, 0), (2, 2));
+        assert_eq!(path.len(), 4);
+        assert_eq!(path, diagonal.dijkstra((0, 0), (2, 2)));
+    }
+
+    #[test]
+    fn heuristics() {
+        let (from, to) = ((0, 0), (3, 4));
+
+        assert!((Distance::Manhattan.between(from, to) - 7.).abs() < 1e-6);
+        assert!((Distance::Chebyshev.between(from, to) - 4.).abs() < 1e-6);
+        assert!((Distance::Euclidean.between(from, to) - 5.).abs() < 1e-6);
+        assert!(
+            (Distance::Octile.between(from, to) - 3_f32.mul_add(2_f32.sqrt(), 1.)).abs() < 1e-6
+        );
+
+        let grid = Grid::filled(5, 5, 2.);
+        assert!((grid.heuristic(&from, &to) - 14.).abs() < 1e-6);
+
+        let grid = grid.with_connectivity(Connectivity::Eight);
+        assert_eq!(grid.distance(), Distance::Octile);
+        let cost: f32 = grid
+            .dijkstra(from, to)
+            .windows(2)
+            .map(|w| grid.cost(&w[0], &w[1]))
+            .sum();
+        assert!((grid.heuristic(&from, &to) - cost).abs() < 1e-4);
+    }
+
+    #[test]
+    fn offsets_stay_in_bounds() {
+        let grid = Grid::filled(3, 2, CellState::Ground);
+
+        assert_eq!(grid.offset((0, 0), (-1, 0)), None);
+        assert_eq!(grid.offset((2, 1), (1, 0)), None);
+        assert_eq!(grid.offset((usize::MAX, 0), (1, 0)), None);
+        assert_eq!(grid.offset((1, 1), (1, -1)), Some((2, 0)));
+        assert_eq!(grid.get((3, 0)), None);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let rows = "X  X\n  XX\nX   ";
+        let grid = maze(rows);
+
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid[(0, 0)], CellState::Wall);
+        assert_eq!(grid.to_string(), rows);
+        assert_eq!(
+            grid.render_path(&grid.a_star((1, 0), (3, 2))),
+            "XS X\n oXX\nXooE"
+        );
+        assert_eq!(
+            grid.render_search(&[(1, 0), (3, 2)], &[(1, 1), (2, 2)]),
+            "XS X\n #XX\nX #E"
+        );
+
+        assert_eq!("".parse::<Grid<CellState>>(), Err(ParseGridError::Empty));
+        assert_eq!(
+            "..\n.".parse::<Grid<CellState>>(),
+            Err(ParseGridError::Ragged { row: 1, length: 1 })
+        );
+        assert_eq!(
+            Grid::parse("1a", |c| if c == '1' { Some(1_u8) } else { None }),
+            Err(ParseGridError::InvalidCell {
+                position: (1, 0),
+                cell: 'a'
+            })
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Hexagonal grids.
+//!
+//! Hexagons are identified by their axial coordinates [`Hex`], the third cube
+//! coordinate of [`Cube`] being implied, which makes neighbours and distances
+//! simple. A [`HexGrid`] stores its cells in a rectangle of offset
+//! coordinates, every other row (or column) being shifted by half a hexagon
+//! according to its [`OffsetLayout`].
+//!
+//! ```
+//! # use state_space::hex::{Hex, HexGrid, OffsetLayout};
+//! # use state_space::grid::CellState;
+//! # use state_space::HeuristicStateSpace;
+//! let map: HexGrid<CellState> = "
+//! . . . .
+//!  . X X .
+//! . . . .
+//! "
+//! .parse()
+//! .unwrap();
+//!
+//! let from = Hex::from_offset((1, 0), OffsetLayout::OddR);
+//! let to = Hex::from_offset((1, 2), OffsetLayout::OddR);
+//! let path = map.a_star(from, to);
+//!
+//! assert_eq!(path.len(), 3);
+//! assert_eq!(map.render_path(&path), ". S . .\n o X X .\n. E . .");
+//! ```
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Index, Sub};
+use std::str::FromStr;
+
+use crate::grid::{CellState, ParseGridError, Terrain};
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// The axial coordinates of a hexagon.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hex {
+    /// The column, increasing towards the east.
+    pub q: i32,
+    /// The row, increasing towards the south-east.
+    pub r: i32,
+}
+
+/// The cube coordinates of a hexagon, summing to zero.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Cube {
+    /// The first coordinate, the axial column.
+    pub q: i32,
+    /// The second coordinate, the axial row.
+    pub r: i32,
+    /// The third coordinate, `-q - r`.
+    pub s: i32,
+}
+
+/// The ways of storing hexagons in a rectangle.
+///
+/// The rows layouts are for pointy-topped hexagons, the odd or even rows
+/// being shifted right, and the column layouts for flat-topped hexagons, the
+/// odd or even columns being shifted down.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OffsetLayout {
+    /// The odd rows are shifted right.
+    OddR,
+    /// The even rows are shifted right.
+    EvenR,
+    /// The odd columns are shifted down.
+    OddQ,
+    /// The even columns are shifted down.
+    EvenQ,
+}
+
+impl Hex {
+    /// The offsets of the six neighbours, starting east and turning
+    /// clockwise for pointy-topped hexagons.
+    pub const DIRECTIONS: [Self; 6] = [
+        Self::new(1, 0),
+        Self::new(0, 1),
+        Self::new(-1, 1),
+        Self::new(-1, 0),
+        Self::new(0, -1),
+        Self::new(1, -1),
+    ];
+
+    /// Creates a hexagon from its axial coordinates.
+    #[inline]
+    #[must_use]
+    pub const fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// Returns the implied third cube coordinate.
+    #[inline]
+    #[must_use]
+    pub const fn s(self) -> i32 {
+        -self.q - self.r
+    }
+
+    /// Returns the six neighbours, in the order of [`Hex::DIRECTIONS`].
+    #[must_use]
+    pub fn neighbours(self) -> [Self; 6] {
+        Self::DIRECTIONS.map(|direction| self + direction)
+    }
+
+    /// Returns the number of moves between `self` and `other`.
+    #[inline]
+    #[must_use]
+    pub const fn distance(self, other: Self) -> u32 {
+        let (dq, dr) = (self.q - other.q, self.r - other.r);
+        (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+    }
+
+    /// Returns the hexagon at the column and row `offset` in `layout`.
+    #[must_use]
+    pub const fn from_offset((col, row): (i32, i32), layout: OffsetLayout) -> Self {
+        match layout {
+            OffsetLayout::OddR => Self::new(col - (row >> 1), row),
+            OffsetLayout::EvenR => Self::new(col - ((row + 1) >> 1), row),
+            OffsetLayout::OddQ => Self::new(col, row - (col >> 1)),
+            OffsetLayout::EvenQ => Self::new(col, row - ((col + 1) >> 1)),
+        }
+    }
+
+    /// Returns the column and row of the hexagon in `layout`.
+    #[must_use]
+    pub const fn to_offset(self, layout: OffsetLayout) -> (i32, i32) {
+        let Self { q, r } = self;
+        match layout {
+            OffsetLayout::OddR => (q + (r >> 1), r),
+            OffsetLayout::EvenR => (q + ((r + 1) >> 1), r),
+            OffsetLayout::OddQ => (q, r + (q >> 1)),
+            OffsetLayout::EvenQ => (q, r + ((q + 1) >> 1)),
+        }
+    }
+}
+
+impl Add for Hex {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        Self::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+impl From<Cube> for Hex {
+    #[inline]
+    fn from(cube: Cube) -> Self {
+        debug_assert_eq!(
+            cube.q + cube.r + cube.s,
+            0,
+            "Cube coordinates should sum to zero"
+        );
+        Self::new(cube.q, cube.r)
+    }
+}
+
+impl From<Hex> for Cube {
+    #[inline]
+    fn from(hex: Hex) -> Self {
+        Self {
+            q: hex.q,
+            r: hex.r,
+            s: hex.s(),
+        }
+    }
+}
+
+/// A hexagonal grid of cells of terrain `T`, stored in a rectangle of offset
+/// coordinates, whose states are the passable hexagons.
+///
+/// Entering a cell costs its [`Terrain::cost`], and the heuristic is the
+/// hexagonal distance times the cheapest cost of the cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HexGrid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    layout: OffsetLayout,
+    /// A lower bound on the cost of the passable cells.
+    min_cost: f32,
+}
+
+impl<T: Terrain> HexGrid<T> {
+    /// Creates a grid of `width` columns and `height` rows in the
+    /// [`OffsetLayout::OddR`] layout from its cells, row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `width * height` cells.
+    #[must_use]
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "A grid should have `width * height` cells"
+        );
+
+        let min_cost = cells
+            .iter()
+            .filter_map(Terrain::cost)
+            .fold(f32::INFINITY, f32::min);
+
+        Self {
+            width,
+            height,
+            cells,
+            layout: OffsetLayout::OddR,
+            min_cost,
+        }
+    }
+
+    /// Creates a grid of `width` columns and `height` rows filled with
+    /// `cell`.
+    #[must_use]
+    pub fn filled(width: usize, height: usize, cell: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![cell; width * height])
+    }
+
+    /// Parses a grid from rows of characters separated by newlines, `cell`
+    /// returning the terrain of each character, or `None` if it is invalid.
+    ///
+    /// Whitespace separates the cells and indents the shifted rows, so it is
+    /// ignored, as are blank lines.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no rows, if rows have different numbers
+    /// of cells or if a character is invalid.
+    pub fn parse(s: &str, mut cell: impl FnMut(char) -> Option<T>) -> Result<Self, ParseGridError> {
+        let rows: Vec<Vec<_>> = s
+            .lines()
+            .map(|row| row.chars().filter(|c| !c.is_whitespace()).collect())
+            .filter(|row: &Vec<_>| !row.is_empty())
+            .collect();
+        let width = rows.first().ok_or(ParseGridError::Empty)?.len();
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(ParseGridError::Ragged {
+                    row: y,
+                    length: row.len(),
+                });
+            }
+
+            for (x, &c) in row.iter().enumerate() {
+                cells.push(cell(c).ok_or(ParseGridError::InvalidCell {
+                    position: (x, y),
+                    cell: c,
+                })?);
+            }
+        }
+
+        Ok(Self::new(width, rows.len(), cells))
+    }
+
+    /// Replaces the cell of `hex`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hex` is out of the grid.
+    pub fn set(&mut self, hex: Hex, cell: T) {
+        let index = self
+            .index_of(hex)
+            .unwrap_or_else(|| panic!("Hexagon {:?} should be in the grid", hex));
+
+        if let Some(cost) = cell.cost() {
+            self.min_cost = self.min_cost.min(cost);
+        }
+        self.cells[index] = cell;
+    }
+
+    /// Returns whether `hex` is in the grid and can be entered.
+    #[inline]
+    #[must_use]
+    pub fn is_passable(&self, hex: Hex) -> bool {
+        self.get(hex).and_then(Terrain::cost).is_some()
+    }
+
+    /// Returns a drawing of the grid, cells being separated by spaces and
+    /// the shifted rows indented, the impassable cells being `X` and the
+    /// passable ones `.`, the hexagons of `path` being `o` apart from its
+    /// first one `S` and its last one `E`.
+    ///
+    /// With the column layouts, the cells are drawn in the rows of their
+    /// offset coordinates, without shifting.
+    #[must_use]
+    pub fn render_path(&self, path: &[Hex]) -> String {
+        let mut drawing = String::with_capacity((2 * self.width + 1) * self.height);
+        for y in 0..self.height {
+            let shifted = match self.layout {
+                OffsetLayout::OddR => y % 2 == 1,
+                OffsetLayout::EvenR => y % 2 == 0,
+                OffsetLayout::OddQ | OffsetLayout::EvenQ => false,
+            };
+            if shifted {
+                drawing.push(' ');
+            }
+
+            for x in 0..self.width {
+                let hex = self.hex_at((x, y));
+                drawing.push(match hex {
+                    hex if !self.is_passable(hex) => 'X',
+                    hex if path.first() == Some(&hex) => 'S',
+                    hex if path.last() == Some(&hex) => 'E',
+                    hex if path.contains(&hex) => 'o',
+                    _ => '.',
+                });
+                drawing.push(' ');
+            }
+            drawing.pop();
+            drawing.push('\n');
+        }
+        drawing.pop();
+
+        drawing
+    }
+}
+
+impl<T> HexGrid<T> {
+    /// Sets the layout of the cells, [`OffsetLayout::OddR`] by default.
+    #[inline]
+    #[must_use]
+    pub const fn with_layout(mut self, layout: OffsetLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the layout of the cells.
+    #[inline]
+    #[must_use]
+    pub const fn layout(&self) -> OffsetLayout {
+        self.layout
+    }
+
+    /// Returns whether `hex` is in the grid.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, hex: Hex) -> bool {
+        self.index_of(hex).is_some()
+    }
+
+    /// Returns the cell of `hex`, if it is in the grid.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, hex: Hex) -> Option<&T> {
+        self.index_of(hex).map(|index| &self.cells[index])
+    }
+
+    /// Returns the hexagon at column `x` and row `y` of the grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates overflow an `i32`.
+    #[must_use]
+    pub fn hex_at(&self, (x, y): (usize, usize)) -> Hex {
+        let offset = (
+            i32::try_from(x).expect("Column should fit in an `i32`"),
+            i32::try_from(y).expect("Row should fit in an `i32`"),
+        );
+        Hex::from_offset(offset, self.layout)
+    }
+
+    fn index_of(&self, hex: Hex) -> Option<usize> {
+        let (x, y) = hex.to_offset(self.layout);
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+
+        if x < self.width && y < self.height {
+            Some(x + y * self.width)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Index<Hex> for HexGrid<T> {
+    type Output = T;
+
+    /// Returns the cell of `hex`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hex` is out of the grid.
+    #[inline]
+    fn index(&self, hex: Hex) -> &T {
+        self.get(hex).expect("Hexagon should be in the grid")
+    }
+}
+
+impl<T: Terrain> StateSpace for HexGrid<T> {
+    type State = Hex;
+
+    fn for_each_neighbour(&self, state: &Hex, f: &mut dyn FnMut(Hex)) {
+        for hex in state.neighbours() {
+            if self.is_passable(hex) {
+                f(hex);
+            }
+        }
+    }
+}
+
+impl<T: Terrain> CostStateSpace for HexGrid<T> {
+    /// Returns the cost of entering `next`, infinite if it cannot be entered.
+    #[inline]
+    fn cost(&self, _current: &Hex, next: &Hex) -> f32 {
+        self.get(*next)
+            .and_then(Terrain::cost)
+            .unwrap_or(f32::INFINITY)
+    }
+}
+
+impl<T: Terrain> HeuristicStateSpace for HexGrid<T> {
+    #[allow(clippy::cast_precision_loss)]
+    #[inline]
+    fn heuristic(&self, state: &Hex, goal: &Hex) -> f32 {
+        if self.min_cost.is_finite() {
+            state.distance(*goal) as f32 * self.min_cost
+        } else {
+            0.
+        }
+    }
+}
+
+/// Parses the ASCII format of [`HexGrid::render_path`], `X` being walls and
+/// any other character ground, in the [`OffsetLayout::OddR`] layout.
+impl FromStr for HexGrid<CellState> {
+    type Err = ParseGridError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, |c| {
+            Some(if c == 'X' {
+                CellState::Wall
+            } else {
+                CellState::Ground
+            })
+        })
+    }
+}
+
+/// Formats the grid as [`HexGrid::render_path`] without a path.
+impl fmt::Display for HexGrid<CellState> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_path(&[]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUTS: [OffsetLayout; 4] = [
+        OffsetLayout::OddR,
+        OffsetLayout::EvenR,
+        OffsetLayout::OddQ,
+        OffsetLayout::EvenQ,
+    ];
+
+    #[test]
+    fn coordinates() {
+        let hex = Hex::new(2, -5);
+
+        assert_eq!(Cube::from(hex), Cube { q: 2, r: -5, s: 3 });
+        assert_eq!(Hex::from(Cube::from(hex)), hex);
+        assert_eq!(hex.distance(Hex::default()), 5);
+        for neighbour in hex.neighbours() {
+            assert_eq!(hex.distance(neighbour), 1);
+            assert_eq!(
+                neighbour.neighbours().iter().filter(|&&n| n == hex).count(),
+                1
+            );
+        }
+
+        for layout in LAYOUTS {
+            for q in -3..3 {
+                for r in -3..3 {
+                    let hex = Hex::new(q, r);
+                    assert_eq!(Hex::from_offset(hex.to_offset(layout), layout), hex);
+                }
+            }
+        }
+        assert_eq!(Hex::new(-1, 3).to_offset(OffsetLayout::OddR), (0, 3));
+        assert_eq!(Hex::new(-2, 3).to_offset(OffsetLayout::EvenR), (0, 3));
+        assert_eq!(Hex::new(3, -1).to_offset(OffsetLayout::OddQ), (3, 0));
+    }
+
+    #[test]
+    fn neighbours_stay_in_the_grid() {
+        for layout in LAYOUTS {
+            let grid = HexGrid::filled(3, 3, CellState::Ground).with_layout(layout);
+
+            assert_eq!(grid.neighbours(&grid.hex_at((1, 1))).len(), 6);
+            for x in 0..3 {
+                for y in 0..3 {
+                    for neighbour in grid.neighbours(&grid.hex_at((x, y))) {
+                        assert!(grid.contains(neighbour));
+                    }
+                }
+            }
+        }
+
+        let grid: HexGrid<CellState> = "X X\n . X\nX X".parse().unwrap();
+        assert_eq!(
+            grid.neighbours(&grid.hex_at((1, 1))),
+            vec![grid.hex_at((0, 1))]
+        );
+    }
+
+    #[test]
+    fn searches_match_distances() {
+        let mut grid = HexGrid::filled(6, 5, 2_u8);
+        grid.set(grid.hex_at((2, 2)), 0);
+        grid.set(grid.hex_at((3, 1)), 5);
+
+        let (from, to) = (grid.hex_at((0, 2)), grid.hex_at((5, 2)));
+        let path = grid.a_star(from, to);
+        let cost: f32 = path.windows(2).map(|w| grid.cost(&w[0], &w[1])).sum();
+
+        assert_eq!(path, grid.dijkstra(from, to));
+        assert!(!path.contains(&grid.hex_at((2, 2))));
+        assert!(grid.heuristic(&from, &to) <= cost);
+        assert!((cost - 12.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let rows = "X . .\n . X .\n. . X";
+        let grid: HexGrid<CellState> = rows.parse().unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[grid.hex_at((1, 1))], CellState::Wall);
+        assert_eq!(grid.to_string(), rows);
+
+        assert_eq!(
+            "  \n. .\n .".parse::<HexGrid<CellState>>(),
+            Err(ParseGridError::Ragged { row: 1, length: 1 })
+        );
+        assert_eq!(
+            "\n".parse::<HexGrid<CellState>>(),
+            Err(ParseGridError::Empty)
+        );
+    }
+}
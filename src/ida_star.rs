@@ -0,0 +1,143 @@
+//! Iterative deepening A*.
+//!
+//! [`ida_star`] runs depth-first searches bounded by *cost + heuristic*,
+//! raising the bound to the lowest value exceeding it after each iteration.
+//! It only stores the current path, at the price of expanding states several
+//! times, which suits state spaces too large for the open list of
+//! [`HeuristicStateSpace::a_star`] with few distinct costs, like puzzles.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, HeuristicStateSpace, StateSpace};
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = i32;
+//! #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+//! # }
+//! # impl CostStateSpace for Line {
+//! #     fn cost(&self, _: &i32, _: &i32) -> f32 { 1. }
+//! # }
+//! # impl HeuristicStateSpace for Line {
+//! #     fn heuristic(&self, state: &i32, goal: &i32) -> f32 { (goal - state).abs() as f32 }
+//! # }
+//! assert_eq!(Line.ida_star(0, 3), vec![0, 1, 2, 3]);
+//! ```
+use crate::search::{SearchResult, SearchStats, Termination};
+use crate::HeuristicStateSpace;
+
+/// The outcome of a bounded depth-first search.
+enum Deepening {
+    /// The goal has been reached with the given cost.
+    Found(f32),
+    /// The goal has not been reached, the lowest *cost + heuristic* above the
+    /// bound being given.
+    Exceeded(f32),
+}
+
+/// Searches a path from `init` to `goal` with iterative deepening A*.
+///
+/// The states of the current path are not expanded again, but other states
+/// may be expanded many times, so the search ends on finite state spaces
+/// only.
+pub fn ida_star<S>(space: &S, init: S::State, goal: &S::State) -> SearchResult<S::State>
+where
+    S: HeuristicStateSpace + ?Sized,
+{
+    let mut stats = SearchStats::default();
+    let mut bound = space.heuristic(&init, goal);
+    let mut path = vec![init];
+
+    loop {
+        match deepen(space, goal, &mut path, 0., bound, &mut stats) {
+            Deepening::Found(cost) => {
+                return SearchResult {
+                    path: Some(path),
+                    cost: Some(cost),
+                    termination: Termination::Found,
+                    stats,
+                }
+            }
+            Deepening::Exceeded(next) if next.is_finite() => bound = next,
+            Deepening::Exceeded(_) => {
+                return SearchResult {
+                    path: None,
+                    cost: None,
+                    termination: Termination::Exhausted,
+                    stats,
+                }
+            }
+        }
+    }
+}
+
+/// Searches depth-first from the last state of `path`, reached with `cost`,
+/// the states whose *cost + heuristic* exceeds `bound`.
+fn deepen<S>(
+    space: &S,
+    goal: &S::State,
+    path: &mut Vec<S::State>,
+    cost: f32,
+    bound: f32,
+    stats: &mut SearchStats,
+) -> Deepening
+where
+    S: HeuristicStateSpace + ?Sized,
+{
+    let current = path.last().expect("Path should not be empty").clone();
+    let estimate = cost + space.heuristic(&current, goal);
+    if estimate > bound {
+        return Deepening::Exceeded(estimate);
+    }
+    if &current == goal {
+        return Deepening::Found(cost);
+    }
+
+    stats.expanded += 1;
+    stats.max_open = stats.max_open.max(path.len());
+
+    let mut next_bound = f32::INFINITY;
+    for neighbour in space.neighbours(&current) {
+        stats.generated += 1;
+        if path.contains(&neighbour) {
+            continue;
+        }
+
+        let next_cost = cost + space.cost(&current, &neighbour);
+        path.push(neighbour);
+        match deepen(space, goal, path, next_cost, bound, stats) {
+            Deepening::Found(cost) => return Deepening::Found(cost),
+            Deepening::Exceeded(estimate) => next_bound = next_bound.min(estimate),
+        }
+        path.pop();
+    }
+
+    Deepening::Exceeded(next_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AStar;
+    use crate::test_utils::Grid;
+    use crate::StateSpace;
+
+    #[test]
+    fn agrees_with_a_star() {
+        let grid = Grid::from_rows(&["......", ".XXXX.", ".X....", ".X.XXX", "......"]);
+
+        let result = ida_star(&grid, (2, 2), &(0, 2));
+        let expected = grid.search().algorithm(AStar).run((2, 2), (0, 2));
+
+        assert_eq!(result.cost, expected.cost);
+        assert_eq!(result.path.map(|path| path.len()), Some(7));
+        assert!(result.stats.max_open <= 7);
+    }
+
+    #[test]
+    fn exhausted_when_unreachable() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+        let result = ida_star(&grid, (0, 0), &(3, 0));
+
+        assert_eq!(result.termination, Termination::Exhausted);
+        assert_eq!(result.path, None);
+    }
+}
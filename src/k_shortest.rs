@@ -0,0 +1,353 @@
+//! Enumerating paths from a state to a goal by nondecreasing cost.
+//!
+//! [`KShortestPaths`] enumerates the paths without repeated states with Yen's
+//! algorithm, while [`KShortestWalks`] enumerates every path, possibly going
+//! through cycles. Both are iterators, computing the next path on demand.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # struct Ring;
+//! # impl StateSpace for Ring {
+//! #     type State = u8;
+//! #     fn neighbours(&self, state: &u8) -> Vec<u8> { vec![(state + 1) % 6, (state + 5) % 6] }
+//! # }
+//! # impl CostStateSpace for Ring {
+//! #     fn cost(&self, _: &u8, _: &u8) -> f32 { 1. }
+//! # }
+//! let paths: Vec<_> = Ring.k_shortest_paths(0, 2).collect();
+//!
+//! assert_eq!(paths, vec![(vec![0, 1, 2], 2.), (vec![0, 5, 4, 3, 2], 4.)]);
+//!
+//! let costs: Vec<_> = Ring.k_shortest_walks(0, 2).map(|(_, cost)| cost).take(6).collect();
+//!
+//! assert_eq!(costs, vec![2., 4., 4., 4., 4., 4.]);
+//! ```
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+
+use crate::algorithm::Dijkstra;
+use crate::arena::{StateArena, StateId};
+use crate::priority_queue::MinPrioriyQueue;
+use crate::search::NoObserver;
+use crate::{CostStateSpace, StateSpace};
+
+/// An iterator over the paths without repeated states from a state to a goal,
+/// by nondecreasing cost, created with
+/// [`CostStateSpace::k_shortest_paths`].
+///
+/// Each path is found with Yen's algorithm, running a Dijkstra search from
+/// each state of the previous path while forbidding the transitions taken by
+/// the paths already found.
+pub struct KShortestPaths<'a, S: CostStateSpace + ?Sized> {
+    space: &'a S,
+    init: S::State,
+    goal: S::State,
+    /// The paths already returned.
+    paths: Vec<Vec<S::State>>,
+    /// The candidate paths along with the bits of their cost.
+    candidates: MinPrioriyQueue<(Vec<S::State>, u32)>,
+    /// The paths ever found, returned or not.
+    seen: HashSet<Vec<S::State>>,
+    started: bool,
+}
+
+impl<'a, S: CostStateSpace + ?Sized> KShortestPaths<'a, S> {
+    /// Creates an iterator over the paths from `init` to `goal`.
+    #[inline]
+    pub fn new(space: &'a S, init: S::State, goal: S::State) -> Self {
+        Self {
+            space,
+            init,
+            goal,
+            paths: Vec::new(),
+            candidates: MinPrioriyQueue::new(),
+            seen: HashSet::new(),
+            started: false,
+        }
+    }
+
+    fn enqueue(&mut self, path: Vec<S::State>, cost: f32) {
+        if self.seen.insert(path.clone()) {
+            self.candidates.enqueue(cost, (path, cost.to_bits()));
+        }
+    }
+
+    /// Finds the candidates deviating from the last path returned.
+    fn deviate(&mut self) {
+        let Some(last) = self.paths.last().cloned() else {
+            return;
+        };
+
+        let mut removed = HashSet::new();
+        let mut root_cost = 0.;
+        for (i, spur) in last.iter().enumerate().take(last.len() - 1) {
+            let root = &last[..=i];
+            let blocked: Vec<_> = self
+                .paths
+                .iter()
+                .filter(|path| path.len() > i + 1 && &path[..=i] == root)
+                .map(|path| path[i + 1].clone())
+                .collect();
+
+            let restricted = Restricted {
+                space: self.space,
+                removed: &removed,
+                spur,
+                blocked: &blocked,
+            };
+            let result = restricted
+                .search()
+                .algorithm(Dijkstra)
+                .observer(NoObserver)
+                .run(spur.clone(), self.goal.clone());
+
+            if let (Some(spur_path), Some(spur_cost)) = (result.path, result.cost) {
+                let mut path = last[..i].to_vec();
+                path.extend(spur_path);
+                self.enqueue(path, root_cost + spur_cost);
+            }
+
+            root_cost += self.space.cost(spur, &last[i + 1]);
+            removed.insert(spur.clone());
+        }
+    }
+}
+
+impl<S: CostStateSpace + ?Sized> Iterator for KShortestPaths<'_, S> {
+    type Item = (Vec<S::State>, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            self.deviate();
+        } else {
+            self.started = true;
+            let result = self
+                .space
+                .search()
+                .algorithm(Dijkstra)
+                .observer(NoObserver)
+                .run(self.init.clone(), self.goal.clone());
+            if let (Some(path), Some(cost)) = (result.path, result.cost) {
+                self.enqueue(path, cost);
+            }
+        }
+
+        let (path, cost) = self.candidates.dequeue()?;
+        self.paths.push(path.clone());
+        Some((path, f32::from_bits(cost)))
+    }
+}
+
+/// A state space without some states and without some transitions from a
+/// state, in which Yen's algorithm searches for deviations.
+struct Restricted<'a, S: StateSpace + ?Sized> {
+    space: &'a S,
+    removed: &'a HashSet<S::State>,
+    spur: &'a S::State,
+    /// The successors of `spur` which cannot be reached from it.
+    blocked: &'a [S::State],
+}
+
+impl<S: StateSpace + ?Sized> StateSpace for Restricted<'_, S> {
+    type State = S::State;
+
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        let from_spur = state == self.spur;
+        self.space.for_each_neighbour(state, &mut |neighbour| {
+            let blocked = from_spur && self.blocked.contains(&neighbour);
+            if !blocked && !self.removed.contains(&neighbour) {
+                f(neighbour);
+            }
+        });
+    }
+}
+
+impl<S: CostStateSpace + ?Sized> CostStateSpace for Restricted<'_, S> {
+    #[inline]
+    fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+        self.space.cost(current, next)
+    }
+}
+
+/// An iterator over the paths from a state to a goal, possibly repeating
+/// states, by nondecreasing cost, created with
+/// [`CostStateSpace::k_shortest_walks`].
+///
+/// The paths are enumerated best-first, sharing their prefixes. Since the
+/// `k`-th cheapest path only goes through prefixes among the `k` cheapest
+/// paths to their last state, each state is only expanded once more than
+/// the number of paths returned, which also ends the iteration once no
+/// path remains in a finite state space.
+///
+/// This is a plain best-first enumeration rather than Eppstein's algorithm
+/// or K*: returning `k` paths of a space with `n` states and `m` edges takes
+/// O(k·(m + n log n)) time, like `k` runs of Dijkstra's algorithm. Every
+/// walk generated is kept until the iterator is dropped, so its memory grows
+/// by up to `m` walks with each path returned.
+pub struct KShortestWalks<'a, S: CostStateSpace + ?Sized> {
+    space: &'a S,
+    goal: S::State,
+    arena: StateArena<S::State, RandomState>,
+    /// The number of times each state has been expanded.
+    expansions: Vec<usize>,
+    walks: Vec<Walk>,
+    open: MinPrioriyQueue<usize>,
+    /// The walks put aside until another path is returned.
+    deferred: Vec<usize>,
+    found: usize,
+}
+
+/// A path to `state`, whose prefix is the walk `parent`.
+struct Walk {
+    state: StateId,
+    parent: Option<usize>,
+    cost: f32,
+}
+
+impl<'a, S: CostStateSpace + ?Sized> KShortestWalks<'a, S> {
+    /// Creates an iterator over the paths from `init` to `goal`.
+    pub fn new(space: &'a S, init: S::State, goal: S::State) -> Self {
+        let mut arena = StateArena::new();
+        let (state, _) = arena.intern(init);
+        let mut open = MinPrioriyQueue::new();
+        open.enqueue(0., 0);
+
+        Self {
+            space,
+            goal,
+            arena,
+            expansions: vec![0],
+            walks: vec![Walk {
+                state,
+                parent: None,
+                cost: 0.,
+            }],
+            open,
+            deferred: Vec::new(),
+            found: 0,
+        }
+    }
+
+    fn path_to(&self, walk: usize) -> Vec<S::State> {
+        let mut path = Vec::new();
+
+        let mut current = Some(walk);
+        while let Some(index) = current {
+            path.push(self.arena[self.walks[index].state].clone());
+            current = self.walks[index].parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+impl<S: CostStateSpace + ?Sized> Iterator for KShortestWalks<'_, S> {
+    type Item = (Vec<S::State>, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let index = self.open.dequeue()?;
+            let Walk {
+                state: id, cost, ..
+            } = self.walks[index];
+
+            if self.expansions[id.index()] > self.found {
+                self.deferred.push(index);
+                continue;
+            }
+            self.expansions[id.index()] += 1;
+
+            let Self {
+                space,
+                arena,
+                expansions,
+                walks,
+                open,
+                ..
+            } = self;
+            let current = arena[id].clone();
+            space.for_each_neighbour(&current, &mut |neighbour| {
+                let next_cost = cost + space.cost(&current, &neighbour);
+                let (state, inserted) = arena.intern(neighbour);
+                if inserted {
+                    expansions.push(0);
+                }
+
+                open.enqueue(next_cost, walks.len());
+                walks.push(Walk {
+                    state,
+                    parent: Some(index),
+                    cost: next_cost,
+                });
+            });
+
+            if current == self.goal {
+                self.found += 1;
+                for deferred in self.deferred.drain(..) {
+                    self.open.enqueue(self.walks[deferred].cost, deferred);
+                }
+
+                return Some((self.path_to(index), cost));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::Grid;
+    use crate::{CostStateSpace, StateSpace};
+
+    #[test]
+    fn loopless_paths_by_cost() {
+        let grid = Grid::open(3, 3);
+        let paths: Vec<_> = grid.k_shortest_paths((0, 0), (2, 2)).take(8).collect();
+
+        assert_eq!(paths.len(), 8);
+        assert!(paths[..6]
+            .iter()
+            .all(|(p, cost)| p.len() == 5 && (*cost - 4.).abs() < 1e-6));
+        assert!(paths[6..]
+            .iter()
+            .all(|(p, cost)| p.len() == 7 && (*cost - 6.).abs() < 1e-6));
+
+        for (i, (path, _)) in paths.iter().enumerate() {
+            let mut states = path.clone();
+            states.sort_unstable();
+            states.dedup();
+            assert_eq!(states.len(), path.len());
+            assert!(!paths[..i].iter().any(|(other, _)| other == path));
+        }
+    }
+
+    #[test]
+    fn loopless_paths_run_out() {
+        let grid = Grid::open(2, 2);
+
+        assert_eq!(grid.k_shortest_paths((0, 0), (1, 1)).count(), 2);
+        assert_eq!(grid.k_shortest_paths((0, 0), (0, 0)).count(), 1);
+    }
+
+    #[test]
+    fn walks_by_cost() {
+        let grid = Grid::from_rows(&["...", ".X.", "..."]);
+        let walks: Vec<_> = grid.k_shortest_walks((0, 0), (2, 0)).take(20).collect();
+
+        assert_eq!(walks[0], (vec![(0, 0), (1, 0), (2, 0)], 2.));
+        assert!(walks.windows(2).all(|w| w[0].1 <= w[1].1));
+        for (walk, _) in &walks {
+            assert!(walk
+                .windows(2)
+                .all(|w| grid.neighbours(&w[0]).contains(&w[1])));
+        }
+    }
+
+    #[test]
+    fn walks_to_unreachable_goal_end() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+
+        assert_eq!(grid.k_shortest_walks((0, 0), (3, 0)).next(), None);
+        assert_eq!(grid.k_shortest_paths((0, 0), (3, 0)).next(), None);
+    }
+}
@@ -8,13 +8,47 @@
     clippy::cargo
 )]
 
-use rand::{thread_rng, Rng};
-use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 
-use priority_queue::MinPrioriyQueue;
-
+use algorithm::{
+    AStar, BreadthFirstSearch, DepthFirstSearch, Dijkstra, GreedySearch, RandomSearch,
+};
+use all_pairs::AllPairs;
+use bellman_ford::NegativeCycle;
+use distance::DistanceMap;
+use k_shortest::{KShortestPaths, KShortestWalks};
+use optimal::OptimalSolutions;
+use search::{Limits, MultiSearchResult, NoObserver, SearchBuilder};
+
+pub mod algorithm;
+pub mod all_pairs;
+pub mod arena;
+pub mod bellman_ford;
+#[cfg(feature = "serde")]
+pub mod checkpoint;
+pub mod codec;
+pub mod distance;
+mod engine;
+pub mod external;
+pub mod graph;
+pub mod grid;
+pub mod hex;
+pub mod ida_star;
+pub mod k_shortest;
+pub mod optimal;
+#[cfg(any(feature = "rayon", feature = "hda"))]
+pub mod parallel;
+pub mod pattern_database;
+#[cfg(feature = "petgraph")]
+pub mod petgraph;
+pub mod portfolio;
 mod priority_queue;
+pub mod search;
+pub mod sliding_puzzle;
+#[cfg(test)]
+mod test_utils;
+mod visited;
+pub mod voxel;
 
 /// A state space which can be searched.
 pub trait StateSpace {
@@ -22,13 +56,42 @@ pub trait StateSpace {
     type State: Clone + Eq + Hash;
 
     /// Returns the state's neighbours.
-    fn neighbours(&self, state: &Self::State) -> Vec<Self::State>;
+    ///
+    /// At least one of [`StateSpace::neighbours`] and
+    /// [`StateSpace::for_each_neighbour`] must be implemented, as each
+    /// defaults to the other.
+    fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
+        let mut neighbours = Vec::new();
+        self.for_each_neighbour(state, &mut |neighbour| neighbours.push(neighbour));
+        neighbours
+    }
+
+    /// Calls `f` on each of the state's neighbours.
+    ///
+    /// The searches generate neighbours through this method, so implementing
+    /// it instead of [`StateSpace::neighbours`] avoids allocating a `Vec` on
+    /// each expansion.
+    #[inline]
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.neighbours(state).into_iter().for_each(f);
+    }
 
     /// A callback used to display the progress of the search algorithm.
     /// It can be used to get a nice animation ;)
+    ///
+    /// It is called after each expansion of the searches observed by
+    /// [`DisplayProgress`](search::DisplayProgress), for instance with
+    /// `space.search().observer(DisplayProgress)`. Since 0.2.0, the searches
+    /// of this trait and its subtraits, such as `a_star`, no longer call it.
     #[inline]
     fn display_progress(&self, _init: &Self::State, _goal: &Self::State, _open: &[Self::State]) {}
 
+    /// Returns a builder configuring a search in the state space.
+    #[inline]
+    fn search(&self) -> SearchBuilder<'_, Self> {
+        SearchBuilder::new(self)
+    }
+
     /// A search expanding nodes randomly.
     ///
     /// # Properties
@@ -37,36 +100,11 @@ pub trait StateSpace {
     /// - Time complexity: O(b^d)
     /// - Space complexity: O(b^d)
     fn random_search(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut rng = thread_rng();
-        let mut open = Vec::new();
-        let mut closed = HashSet::new();
-        let mut parent_of_state = HashMap::new();
-
-        open.push(init.clone());
-
-        while !open.is_empty() {
-            let current = open.remove(rng.gen_range(0..open.len()));
-
-            if current == goal {
-                return reconstruct_path(parent_of_state, current);
-            }
-
-            for neighbour in self.neighbours(&current) {
-                if neighbour != current
-                    && !open.contains(&neighbour)
-                    && !closed.contains(&neighbour)
-                {
-                    open.push(neighbour.clone());
-                    parent_of_state.insert(neighbour, current.clone());
-                }
-            }
-
-            closed.insert(current);
-
-            self.display_progress(&init, &goal, &open);
-        }
-
-        unreachable!()
+        self.search()
+            .algorithm(RandomSearch)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
     }
 
     /// A search expanding nodes level by level.
@@ -77,35 +115,11 @@ pub trait StateSpace {
     /// - Time complexity: O(b^d)
     /// - Space complexity: O(b^d)
     fn breadth_first_search(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut open = VecDeque::new();
-        let mut closed = HashSet::new();
-        let mut parent_of_state = HashMap::new();
-
-        open.push_back(init.clone());
-
-        while !open.is_empty() {
-            let current = open.pop_front().unwrap();
-
-            if current == goal {
-                return reconstruct_path(parent_of_state, current);
-            }
-
-            for neighbour in self.neighbours(&current) {
-                if neighbour != current
-                    && !open.contains(&neighbour)
-                    && !closed.contains(&neighbour)
-                {
-                    open.push_back(neighbour.clone());
-                    parent_of_state.insert(neighbour, current.clone());
-                }
-            }
-
-            closed.insert(current);
-
-            self.display_progress(&init, &goal, open.make_contiguous());
-        }
-
-        unreachable!()
+        self.search()
+            .algorithm(BreadthFirstSearch)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
     }
 
     /// A search expanding a path as far as possible.
@@ -114,52 +128,33 @@ pub trait StateSpace {
     /// - Complete: No
     /// - Optimal: No
     /// - Time complexity: O(b^d)
-    /// - Space complexity: O(bm)
+    /// - Space complexity: O(|S|), O(bm) without a closed set, see
+    ///   [`DuplicateDetection`](crate::search::DuplicateDetection)
     fn depth_first_search(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut open = Vec::new();
-        let mut closed = HashSet::new();
-        let mut parent_of_state = HashMap::new();
-
-        open.push(init.clone());
-
-        while !open.is_empty() {
-            let current = open.pop().unwrap();
-
-            if current == goal {
-                return reconstruct_path(parent_of_state, current);
-            }
-
-            for neighbour in self.neighbours(&current) {
-                if neighbour != current
-                    && !open.contains(&neighbour)
-                    && !closed.contains(&neighbour)
-                {
-                    open.push(neighbour.clone());
-                    parent_of_state.insert(neighbour, current.clone());
-                }
-            }
-
-            closed.insert(current);
-
-            self.display_progress(&init, &goal, &open);
-        }
-
-        unreachable!()
+        self.search()
+            .algorithm(DepthFirstSearch)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
     }
-}
 
-fn reconstruct_path<S: Eq + Hash>(mut parent_of_state: HashMap<S, S>, goal: S) -> Vec<S> {
-    let mut path = Vec::new();
-
-    let mut current_state = goal;
-    while let Some(next_state) = parent_of_state.remove(&current_state) {
-        path.push(current_state);
-        current_state = next_state;
+    /// Returns the number of transitions of the shortest paths from `init` to
+    /// every state reachable within `max_distance` transitions, along with
+    /// their parents.
+    fn distances_from(
+        &self,
+        init: Self::State,
+        max_distance: Option<usize>,
+    ) -> DistanceMap<Self::State> {
+        self.search()
+            .algorithm(BreadthFirstSearch)
+            .observer(NoObserver)
+            .limits(Limits {
+                max_depth: max_distance,
+                ..Limits::default()
+            })
+            .distances(init)
     }
-    path.push(current_state);
-
-    path.reverse();
-    path
 }
 
 /// A state space with a cost function.
@@ -175,43 +170,144 @@ pub trait CostStateSpace: StateSpace {
     /// - Time complexity: O(b^d)
     /// - Space complexity: O(b^d)
     fn dijkstra(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut queue = MinPrioriyQueue::new();
-        let mut distances = HashMap::new();
-        let mut parent_of_state = HashMap::new();
-        let mut closed = HashSet::new();
+        self.search()
+            .algorithm(Dijkstra)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
+    }
 
-        queue.enqueue(0., init.clone());
-        distances.insert(init.clone(), 0.);
-        while !queue.is_empty() {
-            let current = queue.dequeue().unwrap();
+    /// A search keeping every path with minimum cost from `init` to `goal`,
+    /// see [`OptimalSolutions`].
+    ///
+    /// # Properties
+    /// - Complete: Yes
+    /// - Optimal: Yes
+    /// - Time complexity: O(b^d)
+    /// - Space complexity: O(b^d)
+    fn dijkstra_all(&self, init: Self::State, goal: Self::State) -> OptimalSolutions<Self::State> {
+        OptimalSolutions::new(self, &Dijkstra, init, &goal)
+    }
 
-            if current == goal {
-                return reconstruct_path(parent_of_state, goal);
-            }
+    /// Returns the costs of the cheapest paths from `init` to every state
+    /// reachable within `max_cost`, along with their parents.
+    fn cost_distances_from(
+        &self,
+        init: Self::State,
+        max_cost: Option<f32>,
+    ) -> DistanceMap<Self::State> {
+        self.search()
+            .algorithm(Dijkstra)
+            .observer(NoObserver)
+            .limits(Limits {
+                max_cost,
+                ..Limits::default()
+            })
+            .distances(init)
+    }
 
-            let current_dist = *distances.get(&current).unwrap();
-            for neighbour in self.neighbours(&current) {
-                let d = current_dist + self.cost(&current, &neighbour);
+    /// A search from the closest of `sources` to the closest of `goals`,
+    /// returning which of them the cheapest path connects.
+    ///
+    /// # Properties
+    /// - Complete: Yes
+    /// - Optimal: Yes
+    /// - Time complexity: O(b^d)
+    /// - Space complexity: O(b^d)
+    fn nearest(
+        &self,
+        sources: Vec<Self::State>,
+        goals: Vec<Self::State>,
+    ) -> MultiSearchResult<Self::State> {
+        self.search()
+            .algorithm(Dijkstra)
+            .observer(NoObserver)
+            .run_many(sources.into_iter().map(|source| (source, 0.)), goals)
+    }
 
-                if distances.contains_key(&neighbour) {
-                    let dp = distances.get_mut(&neighbour).unwrap();
-                    if d < *dp {
-                        *dp = d;
-                        queue.enqueue(d, neighbour.clone());
-                        parent_of_state.insert(neighbour, current.clone());
-                    }
-                } else {
-                    distances.insert(neighbour.clone(), d);
-                    queue.enqueue(d, neighbour.clone());
-                    parent_of_state.insert(neighbour, current.clone());
-                }
-            }
+    /// Returns the costs of the cheapest paths from the closest of `sources`
+    /// to every state reachable within `max_cost`, along with their parents
+    /// and the sources owning them.
+    fn voronoi(
+        &self,
+        sources: Vec<Self::State>,
+        max_cost: Option<f32>,
+    ) -> DistanceMap<Self::State> {
+        self.search()
+            .algorithm(Dijkstra)
+            .observer(NoObserver)
+            .limits(Limits {
+                max_cost,
+                ..Limits::default()
+            })
+            .distances_many(sources.into_iter().map(|source| (source, 0.)))
+    }
 
-            closed.insert(current);
-            self.display_progress(&init, &goal, &Vec::from(queue.clone()));
-        }
+    /// Returns the costs of the cheapest paths from `init` to every state
+    /// reachable from it, along with their parents, allowing negative costs,
+    /// see [`bellman_ford`](bellman_ford::bellman_ford).
+    ///
+    /// # Errors
+    ///
+    /// Returns a cycle of negative cost if one is reachable from `init`.
+    ///
+    /// # Properties
+    /// - Complete: Yes (if the state space is finite)
+    /// - Optimal: Yes
+    /// - Time complexity: O(|S| |T|)
+    /// - Space complexity: O(|S|)
+    #[inline]
+    fn bellman_ford(
+        &self,
+        init: Self::State,
+    ) -> Result<DistanceMap<Self::State>, NegativeCycle<Self::State>> {
+        bellman_ford::bellman_ford(self, init)
+    }
+
+    /// Returns the distances between every pair of states reachable from
+    /// `roots`, with Johnson's algorithm, see [`AllPairs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a cycle of negative cost if one is reachable from the roots.
+    ///
+    /// # Properties
+    /// - Complete: Yes (if the state space is finite)
+    /// - Optimal: Yes
+    /// - Time complexity: O(|S| |T| log |S|)
+    /// - Space complexity: O(|S|^2)
+    #[inline]
+    fn all_pairs(
+        &self,
+        roots: Vec<Self::State>,
+    ) -> Result<AllPairs<Self::State>, NegativeCycle<Self::State>> {
+        AllPairs::johnson(self, roots)
+    }
 
-        unreachable!()
+    /// Returns an iterator over the paths without repeated states from
+    /// `init` to `goal` by nondecreasing cost, with Yen's algorithm.
+    ///
+    /// # Properties
+    /// - Complete: Yes
+    /// - Optimal: Yes
+    /// - Time complexity: O(kd) Dijkstra searches for k paths
+    /// - Space complexity: O(b^d + kd)
+    #[inline]
+    fn k_shortest_paths(&self, init: Self::State, goal: Self::State) -> KShortestPaths<'_, Self> {
+        KShortestPaths::new(self, init, goal)
+    }
+
+    /// Returns an iterator over the paths from `init` to `goal`, possibly
+    /// going through cycles, by nondecreasing cost.
+    ///
+    /// # Properties
+    /// - Complete: Yes
+    /// - Optimal: Yes
+    /// - Time complexity: O(k b |S|) for k paths
+    /// - Space complexity: O(k b |S|) for k paths
+    #[inline]
+    fn k_shortest_walks(&self, init: Self::State, goal: Self::State) -> KShortestWalks<'_, Self> {
+        KShortestWalks::new(self, init, goal)
     }
 }
 
@@ -229,35 +325,11 @@ pub trait HeuristicStateSpace: CostStateSpace {
     /// - Time complexity: O(b^d)
     /// - Space complexity: O(b^d)
     fn greedy_search(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut open = MinPrioriyQueue::new();
-        let mut closed = HashSet::new();
-        let mut parent_of_state = HashMap::new();
-
-        open.enqueue(self.heuristic(&init, &goal), init.clone());
-
-        while !open.is_empty() {
-            let current = open.dequeue().unwrap();
-
-            if current == goal {
-                return reconstruct_path(parent_of_state, current);
-            }
-
-            for neighbour in self.neighbours(&current) {
-                if neighbour != current
-                    && !open.contains(&neighbour)
-                    && !closed.contains(&neighbour)
-                {
-                    open.enqueue(self.heuristic(&neighbour, &goal), neighbour.clone());
-                    parent_of_state.insert(neighbour, current.clone());
-                }
-            }
-
-            closed.insert(current);
-
-            self.display_progress(&init, &goal, &Vec::from(open.clone()));
-        }
-
-        unreachable!()
+        self.search()
+            .algorithm(GreedySearch)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
     }
 
     /// A search expanding nodes with minimum *cost + heuristic*.
@@ -268,47 +340,38 @@ pub trait HeuristicStateSpace: CostStateSpace {
     /// - Time complexity: O(min(b^(d+1), b|S|))
     /// - Space complexity: O(min(b^(d+1), b|S|))
     fn a_star(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
-        let mut open = MinPrioriyQueue::new();
-        let mut distances = HashMap::new();
-        let mut parent_of_state = HashMap::new();
-        let mut closed = HashSet::new();
-
-        open.enqueue(self.heuristic(&init, &goal), init.clone());
-        distances.insert(init.clone(), 0.);
-
-        while !open.is_empty() {
-            let current = open.dequeue().unwrap();
-
-            if current == goal {
-                return reconstruct_path(parent_of_state, goal);
-            }
-
-            let current_dist = *distances.get(&current).unwrap();
-            for neighbour in self
-                .neighbours(&current)
-                .into_iter()
-                .filter(|n| !closed.contains(n))
-            {
-                let neighbour_dist = current_dist + self.cost(&current, &neighbour);
-
-                if !open.contains(&neighbour)
-                    || !distances.contains_key(&neighbour)
-                    || *distances.get(&neighbour).unwrap() > neighbour_dist
-                {
-                    distances.insert(neighbour.clone(), neighbour_dist);
-                    parent_of_state.insert(neighbour.clone(), current.clone());
-
-                    open.enqueue(
-                        neighbour_dist + self.heuristic(&neighbour, &goal),
-                        neighbour,
-                    );
-                }
-            }
+        self.search()
+            .algorithm(AStar)
+            .run(init, goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
+    }
 
-            closed.insert(current);
-            self.display_progress(&init, &goal, &Vec::from(open.clone()));
-        }
+    /// A search keeping every path with minimum *cost* from `init` to
+    /// `goal`, expanding nodes with minimum *cost + heuristic*, see
+    /// [`OptimalSolutions`].
+    ///
+    /// # Properties
+    /// - Complete: Yes
+    /// - Optimal: Yes (if the heuristic is *optimistic*)
+    /// - Time complexity: O(min(b^(d+1), b|S|))
+    /// - Space complexity: O(min(b^(d+1), b|S|))
+    fn a_star_all(&self, init: Self::State, goal: Self::State) -> OptimalSolutions<Self::State> {
+        OptimalSolutions::new(self, &AStar, init, &goal)
+    }
 
-        unreachable!()
+    /// A search expanding nodes depth-first while their *cost + heuristic*
+    /// is within a bound, raised until the goal is reached, see
+    /// [`ida_star`](ida_star::ida_star).
+    ///
+    /// # Properties
+    /// - Complete: Yes (if the state space is finite)
+    /// - Optimal: Yes (if the heuristic is *optimistic*)
+    /// - Time complexity: O(b^d) per iteration
+    /// - Space complexity: O(d)
+    fn ida_star(&self, init: Self::State, goal: Self::State) -> Vec<Self::State> {
+        ida_star::ida_star(self, init, &goal)
+            .into_path()
+            .expect("Goal should be reachable from the initial state")
     }
 }
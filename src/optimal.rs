@@ -0,0 +1,342 @@
+//! Every optimal solution of a search.
+//!
+//! Unlike [`CostStateSpace::dijkstra`](crate::CostStateSpace::dijkstra) and
+//! [`HeuristicStateSpace::a_star`](crate::HeuristicStateSpace::a_star),
+//! returning a single path, the search keeps every parent through which a
+//! state is reached with its optimal cost, forming a graph of the optimal
+//! paths. They can then be counted without being built, or enumerated one at
+//! a time.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, StateSpace};
+//! # struct Plane;
+//! # impl StateSpace for Plane {
+//! #     type State = (i32, i32);
+//! #     fn neighbours(&self, &(x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+//! #         vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+//! #     }
+//! # }
+//! # impl CostStateSpace for Plane {
+//! #     fn cost(&self, _: &(i32, i32), _: &(i32, i32)) -> f32 { 1. }
+//! # }
+//! let solutions = Plane.dijkstra_all((0, 0), (2, 1));
+//!
+//! assert_eq!(solutions.cost(), Some(3.));
+//! assert_eq!(solutions.count(), 3);
+//! assert_eq!(solutions.paths().count(), 3);
+//! ```
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+use crate::algorithm::SearchAlgorithm;
+use crate::arena::{StateArena, StateId};
+use crate::priority_queue::MinPrioriyQueue;
+use crate::search::SearchStats;
+use crate::StateSpace;
+
+/// The optimal paths from a state to a goal.
+///
+/// They are searched with
+/// [`CostStateSpace::dijkstra_all`](crate::CostStateSpace::dijkstra_all),
+/// [`HeuristicStateSpace::a_star_all`](crate::HeuristicStateSpace::a_star_all)
+/// or [`OptimalSolutions::new`].
+///
+/// The costs of the paths are compared exactly, so the costs of the
+/// transitions should be exactly representable, like integers, for paths
+/// summing them in different orders to be considered equal. They should also
+/// be positive, as cycles of zero cost would make the paths infinitely many.
+pub struct OptimalSolutions<T, H = RandomState> {
+    arena: StateArena<T, H>,
+    /// The best costs indexed by the identifiers of the states.
+    costs: Vec<f32>,
+    /// The parents through which the states are reached with their best
+    /// costs.
+    parents: Vec<Vec<StateId>>,
+    goal: Option<StateId>,
+    stats: SearchStats,
+}
+
+impl<T: Clone + Eq + Hash> OptimalSolutions<T> {
+    /// Searches every optimal path from `init` to `goal`, expanding states by
+    /// the priority given by `algorithm`.
+    ///
+    /// The paths are optimal if `algorithm` expands states by cost, or by
+    /// cost plus an optimistic heuristic like [`AStar`](crate::algorithm::AStar),
+    /// whatever the order of its frontier.
+    #[allow(clippy::float_cmp)]
+    pub fn new<S, A>(space: &S, algorithm: &A, init: T, goal: &T) -> Self
+    where
+        S: StateSpace<State = T> + ?Sized,
+        A: SearchAlgorithm<S> + ?Sized,
+    {
+        let mut arena = StateArena::new();
+        let mut costs = vec![0.];
+        let mut parents = vec![Vec::new()];
+        let mut expanded = vec![false];
+        let mut stats = SearchStats::default();
+        let mut best = None;
+
+        let mut open = MinPrioriyQueue::new();
+        let priority = algorithm.priority(space, &init, goal, 0.);
+        let (root, _) = arena.intern(init);
+        open.enqueue(priority, (root, 0_f32.to_bits(), priority.to_bits()));
+
+        while let Some((id, cost, priority)) = open.dequeue() {
+            let (cost, priority) = (f32::from_bits(cost), f32::from_bits(priority));
+            // The states of the optimal paths have a priority at most their
+            // cost.
+            if best.is_some_and(|(_, best)| priority > best) {
+                break;
+            }
+            if cost > costs[id.index()] || expanded[id.index()] {
+                continue;
+            }
+            expanded[id.index()] = true;
+
+            let current = arena[id].clone();
+            if &current == goal {
+                best = Some((id, cost));
+                continue;
+            }
+            stats.expanded += 1;
+
+            space.for_each_neighbour(&current, &mut |neighbour| {
+                stats.generated += 1;
+
+                let next_cost = cost + algorithm.cost(space, &current, &neighbour);
+                let next_priority = algorithm.priority(space, &neighbour, goal, next_cost);
+                let (next, inserted) = arena.intern(neighbour);
+                if inserted {
+                    costs.push(next_cost);
+                    parents.push(vec![id]);
+                    expanded.push(false);
+                } else if next_cost < costs[next.index()] {
+                    costs[next.index()] = next_cost;
+                    parents[next.index()] = vec![id];
+                    expanded[next.index()] = false;
+                } else {
+                    if next_cost == costs[next.index()] {
+                        parents[next.index()].push(id);
+                    }
+                    return;
+                }
+
+                open.enqueue(
+                    next_priority,
+                    (next, next_cost.to_bits(), next_priority.to_bits()),
+                );
+            });
+
+            stats.max_open = stats.max_open.max(open.len());
+        }
+
+        Self {
+            arena,
+            costs,
+            parents,
+            goal: best.map(|(goal, _)| goal),
+            stats,
+        }
+    }
+}
+
+impl<T, H> OptimalSolutions<T, H> {
+    /// Returns the cost of the optimal paths, if the goal is reachable.
+    #[inline]
+    #[must_use]
+    pub fn cost(&self) -> Option<f32> {
+        self.goal.map(|goal| self.costs[goal.index()])
+    }
+
+    /// Returns the statistics of the search.
+    #[inline]
+    #[must_use]
+    pub const fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Returns the number of optimal paths, saturating at [`u128::MAX`],
+    /// without building them.
+    #[must_use]
+    pub fn count(&self) -> u128 {
+        let Some(goal) = self.goal else {
+            return 0;
+        };
+
+        // The states of the optimal paths, parents having lower costs than
+        // their children.
+        let mut states = vec![goal];
+        let mut reached = vec![false; self.arena.len()];
+        reached[goal.index()] = true;
+        let mut i = 0;
+        while let Some(&id) = states.get(i) {
+            for &parent in &self.parents[id.index()] {
+                if !reached[parent.index()] {
+                    reached[parent.index()] = true;
+                    states.push(parent);
+                }
+            }
+            i += 1;
+        }
+        states.sort_by(|a, b| self.costs[a.index()].total_cmp(&self.costs[b.index()]));
+
+        let mut counts = vec![0_u128; self.arena.len()];
+        for id in states {
+            let parents = &self.parents[id.index()];
+            counts[id.index()] = if parents.is_empty() {
+                1
+            } else {
+                parents.iter().fold(0, |count: u128, parent| {
+                    count.saturating_add(counts[parent.index()])
+                })
+            };
+        }
+
+        counts[goal.index()]
+    }
+
+    /// Returns an iterator over the optimal paths, built one at a time.
+    #[inline]
+    #[must_use]
+    pub const fn paths(&self) -> OptimalPaths<'_, T, H> {
+        OptimalPaths {
+            solutions: self,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+}
+
+impl<T: Eq + Hash, H: BuildHasher> OptimalSolutions<T, H> {
+    /// Returns the parents through which `state` is reached with its optimal
+    /// cost, empty if it is the initial state or has not been reached.
+    pub fn parents(&self, state: &T) -> impl Iterator<Item = &T> {
+        self.arena
+            .get(state)
+            .map_or(&[][..], |id| &self.parents[id.index()])
+            .iter()
+            .map(move |&parent| &self.arena[parent])
+    }
+}
+
+/// An iterator over the optimal paths of [`OptimalSolutions`], created with
+/// [`OptimalSolutions::paths`].
+pub struct OptimalPaths<'a, T, H = RandomState> {
+    solutions: &'a OptimalSolutions<T, H>,
+    /// The states of the current path from the goal along with the index of
+    /// their parent on it.
+    stack: Vec<(StateId, usize)>,
+    started: bool,
+}
+
+impl<T, H> OptimalPaths<'_, T, H> {
+    /// Follows the first parents from `id` up to the initial state.
+    fn descend(&mut self, mut id: StateId) {
+        loop {
+            self.stack.push((id, 0));
+            match self.solutions.parents[id.index()].first() {
+                Some(&parent) => id = parent,
+                None => return,
+            }
+        }
+    }
+
+    /// Moves to the next path, returning whether there is one.
+    fn advance(&mut self) -> bool {
+        if !self.started {
+            self.started = true;
+            if let Some(goal) = self.solutions.goal {
+                self.descend(goal);
+                return true;
+            }
+            return false;
+        }
+
+        while let Some((id, parent)) = self.stack.pop() {
+            if let Some(&next) = self.solutions.parents[id.index()].get(parent + 1) {
+                self.stack.push((id, parent + 1));
+                self.descend(next);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<T: Clone, H> Iterator for OptimalPaths<'_, T, H> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.advance() {
+            return None;
+        }
+
+        let arena = &self.solutions.arena;
+        Some(
+            self.stack
+                .iter()
+                .rev()
+                .map(|&(id, _)| arena[id].clone())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::BreadthFirstSearch;
+    use crate::test_utils::Grid;
+    use crate::{CostStateSpace, HeuristicStateSpace};
+
+    use super::OptimalSolutions;
+
+    #[test]
+    fn lattice_paths() {
+        let grid = Grid::open(5, 4);
+        let solutions = grid.a_star_all((0, 0), (4, 3));
+
+        // Choosing which 3 of the 7 moves go down.
+        assert_eq!(solutions.cost(), Some(7.));
+        assert_eq!(solutions.count(), 35);
+
+        let paths: Vec<_> = solutions.paths().collect();
+        assert_eq!(paths.len(), 35);
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(path.len(), 8);
+            assert_eq!((path[0], path[7]), ((0, 0), (4, 3)));
+            assert!(!paths[..i].contains(path));
+        }
+
+        assert_eq!(solutions.parents(&(1, 1)).count(), 2);
+        assert_eq!(solutions.parents(&(0, 0)).count(), 0);
+    }
+
+    #[test]
+    fn a_star_expands_less() {
+        let grid = Grid::from_rows(&[
+            "..........",
+            ".XXXXXXXX.",
+            ".X......X.",
+            ".X.XXXX.X.",
+            "...X......",
+        ]);
+
+        let a_star = grid.a_star_all((0, 0), (9, 4));
+        let dijkstra = grid.dijkstra_all((0, 0), (9, 4));
+        let bfs = OptimalSolutions::new(&grid, &BreadthFirstSearch, (0, 0), &(9, 4));
+
+        assert_eq!(a_star.count(), dijkstra.count());
+        assert_eq!(bfs.count(), dijkstra.count());
+        assert!(a_star.stats().expanded < dijkstra.stats().expanded);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+        let solutions = grid.dijkstra_all((0, 0), (3, 0));
+
+        assert_eq!(solutions.cost(), None);
+        assert_eq!(solutions.count(), 0);
+        assert_eq!(solutions.paths().next(), None);
+    }
+}
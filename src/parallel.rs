@@ -0,0 +1,10 @@
+//! Searches spreading the expansions over several threads.
+#[cfg(feature = "rayon")]
+mod bfs;
+#[cfg(feature = "hda")]
+mod hda;
+
+#[cfg(feature = "rayon")]
+pub use bfs::breadth_first_search;
+#[cfg(feature = "hda")]
+pub use hda::{hda_star, HdaResult, WorkerStats};
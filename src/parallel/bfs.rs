@@ -0,0 +1,165 @@
+use rayon::prelude::*;
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::search::{SearchResult, SearchStats, Termination};
+use crate::StateSpace;
+
+/// A breadth-first search expanding each layer in parallel with rayon.
+///
+/// The states of a layer are expanded concurrently and their successors are
+/// deduplicated through a visited set sharded by hash, so the path found is
+/// as short as the one of [`StateSpace::breadth_first_search`], although
+/// possibly a different one.
+///
+/// # Properties
+/// - Complete: Yes
+/// - Optimal: Yes
+/// - Time complexity: O(b^d)
+/// - Space complexity: O(b^d)
+pub fn breadth_first_search<S>(space: &S, init: S::State, goal: S::State) -> SearchResult<S::State>
+where
+    S: StateSpace + Sync + ?Sized,
+    S::State: Send + Sync,
+{
+    let visited = ShardedMap::new(4 * rayon::current_num_threads());
+    let mut stats = SearchStats::default();
+
+    visited.insert(init.clone(), None);
+    let mut layer = vec![init];
+
+    while !layer.is_empty() {
+        if layer.contains(&goal) {
+            let path = visited.path_to(goal);
+            #[allow(clippy::cast_precision_loss)]
+            let cost = (path.len() - 1) as f32;
+
+            return SearchResult {
+                path: Some(path),
+                cost: Some(cost),
+                termination: Termination::Found,
+                stats,
+            };
+        }
+
+        stats.expanded += layer.len();
+        stats.max_open = stats.max_open.max(layer.len());
+
+        let generated = AtomicUsize::new(0);
+        layer = layer
+            .par_iter()
+            .flat_map_iter(|current| {
+                let mut next = Vec::new();
+                let mut count = 0;
+                space.for_each_neighbour(current, &mut |neighbour| {
+                    count += 1;
+                    if visited.insert(neighbour.clone(), Some(current.clone())) {
+                        next.push(neighbour);
+                    }
+                });
+
+                generated.fetch_add(count, Ordering::Relaxed);
+                next
+            })
+            .collect();
+
+        stats.generated += generated.into_inner();
+    }
+
+    SearchResult {
+        path: None,
+        cost: None,
+        termination: Termination::Exhausted,
+        stats,
+    }
+}
+
+/// A map from the states reached to their parents, split in shards locked
+/// independently.
+struct ShardedMap<T> {
+    shards: Vec<Mutex<HashMap<T, Option<T>>>>,
+    hasher: RandomState,
+}
+
+impl<T: Clone + Eq + Hash> ShardedMap<T> {
+    fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard(&self, state: &T) -> &Mutex<HashMap<T, Option<T>>> {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = self.hasher.hash_one(state) as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    /// Records the parent of `state`, returning whether it was not reached
+    /// yet.
+    fn insert(&self, state: T, parent: Option<T>) -> bool {
+        let mut shard = self.shard(&state).lock().unwrap();
+        match shard.entry(state) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(parent);
+                true
+            }
+        }
+    }
+
+    fn path_to(&self, goal: T) -> Vec<T> {
+        let mut path = Vec::new();
+
+        let mut current = Some(goal);
+        while let Some(state) = current {
+            let parent = self.shard(&state).lock().unwrap()[&state].clone();
+            path.push(state);
+            current = parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+
+    #[test]
+    fn same_length_as_sequential() {
+        let grid = Grid::from_rows(&[
+            "........", ".XXXXXX.", ".X....X.", ".X.XX.X.", ".X.X..X.", "...X....",
+        ]);
+
+        let parallel = breadth_first_search(&grid, (0, 0), (4, 4));
+        let sequential = grid.breadth_first_search((0, 0), (4, 4));
+
+        assert_eq!(parallel.path.unwrap().len(), sequential.len());
+    }
+
+    #[test]
+    fn path_is_connected() {
+        let grid = Grid::open(30, 30);
+        let path = breadth_first_search(&grid, (3, 7), (25, 12)).path.unwrap();
+
+        assert_eq!(path.len(), 22 + 5 + 1);
+        assert!(path
+            .windows(2)
+            .all(|w| grid.neighbours(&w[0]).contains(&w[1])));
+    }
+
+    #[test]
+    fn exhausted_when_unreachable() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+        let result = breadth_first_search(&grid, (0, 0), (3, 0));
+
+        assert_eq!(result.termination, Termination::Exhausted);
+        assert_eq!(result.stats.expanded, 4);
+    }
+}
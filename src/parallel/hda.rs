@@ -0,0 +1,400 @@
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::priority_queue::MinPrioriyQueue;
+use crate::search::{SearchResult, SearchStats, Termination};
+use crate::HeuristicStateSpace;
+
+/// Statistics of a worker of [`hda_star`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// The number of states expanded by the worker.
+    pub expanded: usize,
+    /// The number of successors generated by the worker.
+    pub generated: usize,
+    /// The number of states sent to other workers.
+    pub sent: usize,
+    /// The number of states received from other workers.
+    pub received: usize,
+}
+
+/// The outcome of [`hda_star`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdaResult<T> {
+    /// The outcome of the search, its statistics summing the ones of the
+    /// workers.
+    pub search: SearchResult<T>,
+    /// The statistics of each worker.
+    pub workers: Vec<WorkerStats>,
+}
+
+impl<T> HdaResult<T> {
+    /// Returns the ratio of the largest number of expansions of a worker to
+    /// the mean, 1 meaning a perfectly balanced load.
+    #[must_use]
+    pub fn load_imbalance(&self) -> f64 {
+        let max = self.workers.iter().map(|w| w.expanded).max().unwrap_or(0);
+        let total: usize = self.workers.iter().map(|w| w.expanded).sum();
+
+        if total == 0 {
+            1.
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            let imbalance = (max * self.workers.len()) as f64 / total as f64;
+            imbalance
+        }
+    }
+}
+
+/// A hash-distributed A* running on `threads` threads.
+///
+/// Each state is owned by a worker chosen by its hash, which keeps its open
+/// and closed lists. Successors are sent to their owner through channels, and
+/// the search ends once no worker can expand a state cheaper than the best
+/// solution found and no message is in flight.
+///
+/// # Properties
+/// - Complete: Yes
+/// - Optimal: Yes (if the heuristic is *optimistic*)
+/// - Time complexity: O(min(b^(d+1), b|S|))
+/// - Space complexity: O(min(b^(d+1), b|S|))
+///
+/// # Panics
+///
+/// Panics if `threads` is zero.
+pub fn hda_star<S>(space: &S, init: S::State, goal: S::State, threads: usize) -> HdaResult<S::State>
+where
+    S: HeuristicStateSpace + Sync + ?Sized,
+    S::State: Send + Sync,
+{
+    assert!(threads > 0, "HDA* should run on at least one thread");
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..threads).map(|_| mpsc::channel()).unzip();
+    let shared = Shared {
+        hasher: RandomState::new(),
+        incumbent: AtomicU32::new(f32::INFINITY.to_bits()),
+        // The initial state is in flight.
+        pending: AtomicUsize::new(1),
+    };
+    senders[shared.owner(&init, threads)]
+        .send(Message {
+            state: init.clone(),
+            cost: 0.,
+            parent: None,
+        })
+        .unwrap();
+
+    let workers: Vec<Worker<S::State>> = thread::scope(|scope| {
+        let handles: Vec<_> = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(id, receiver)| {
+                let mut worker = Worker {
+                    id,
+                    senders: senders.clone(),
+                    receiver,
+                    open: MinPrioriyQueue::new(),
+                    closed: HashMap::new(),
+                    active: false,
+                    stats: WorkerStats::default(),
+                };
+                let shared = &shared;
+                let goal = &goal;
+
+                scope.spawn(move || {
+                    worker.run(space, goal, shared);
+                    worker
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut stats = SearchStats::default();
+    for worker in &workers {
+        stats.expanded += worker.stats.expanded;
+        stats.generated += worker.stats.generated;
+    }
+
+    let incumbent = f32::from_bits(shared.incumbent.load(Ordering::SeqCst));
+    let search = if incumbent.is_finite() {
+        let mut path = Vec::new();
+
+        let mut current = Some(goal);
+        while let Some(state) = current {
+            let owner = &workers[shared.owner(&state, threads)];
+            let parent = owner.closed[&state].1.clone();
+            path.push(state);
+            current = parent;
+        }
+        path.reverse();
+
+        SearchResult {
+            path: Some(path),
+            cost: Some(incumbent),
+            termination: Termination::Found,
+            stats,
+        }
+    } else {
+        SearchResult {
+            path: None,
+            cost: None,
+            termination: Termination::Exhausted,
+            stats,
+        }
+    };
+
+    HdaResult {
+        search,
+        workers: workers.into_iter().map(|w| w.stats).collect(),
+    }
+}
+
+/// The state shared by the workers.
+struct Shared {
+    hasher: RandomState,
+    /// The bits of the cost of the best solution found.
+    incumbent: AtomicU32,
+    /// The number of messages in flight plus the number of workers having
+    /// states to expand, the search being over once it drops to zero.
+    pending: AtomicUsize,
+}
+
+impl Shared {
+    fn owner<T: Hash>(&self, state: &T, threads: usize) -> usize {
+        #[allow(clippy::cast_possible_truncation)]
+        let owner = self.hasher.hash_one(state) as usize % threads;
+        owner
+    }
+
+    fn incumbent(&self) -> f32 {
+        f32::from_bits(self.incumbent.load(Ordering::SeqCst))
+    }
+
+    fn improve_incumbent(&self, cost: f32) {
+        // Costs being non-negative, their bits are ordered like them.
+        self.incumbent.fetch_min(cost.to_bits(), Ordering::SeqCst);
+    }
+}
+
+/// A state reached with `cost` from `parent`, sent to its owner.
+struct Message<T> {
+    state: T,
+    cost: f32,
+    parent: Option<T>,
+}
+
+struct Worker<T> {
+    id: usize,
+    senders: Vec<Sender<Message<T>>>,
+    receiver: Receiver<Message<T>>,
+    /// The states to expand along with the bits of their cost.
+    open: MinPrioriyQueue<(T, u32)>,
+    /// The best cost and parent of the states owned by the worker.
+    closed: HashMap<T, (f32, Option<T>)>,
+    /// Whether the worker has states to expand, counted in
+    /// `Shared::pending`.
+    active: bool,
+    stats: WorkerStats,
+}
+
+impl<T: Clone + Eq + Hash> Worker<T> {
+    fn run<S>(&mut self, space: &S, goal: &T, shared: &Shared)
+    where
+        S: HeuristicStateSpace<State = T> + ?Sized,
+    {
+        loop {
+            while let Ok(message) = self.receiver.try_recv() {
+                self.receive(space, goal, shared, message);
+            }
+
+            if let Some((state, cost)) = self.open.dequeue() {
+                self.expand(space, goal, shared, &state, f32::from_bits(cost));
+                continue;
+            }
+
+            if self.active {
+                self.active = false;
+                shared.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+            if shared.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+
+            match self.receiver.recv_timeout(Duration::from_millis(1)) {
+                Ok(message) => self.receive(space, goal, shared, message),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn receive<S>(&mut self, space: &S, goal: &T, shared: &Shared, message: Message<T>)
+    where
+        S: HeuristicStateSpace<State = T> + ?Sized,
+    {
+        self.stats.received += 1;
+        self.relax(space, goal, shared, message);
+        // Decremented after `relax` may have activated the worker, so that
+        // `pending` does not drop to zero while work remains.
+        shared.pending.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn relax<S>(&mut self, space: &S, goal: &T, shared: &Shared, message: Message<T>)
+    where
+        S: HeuristicStateSpace<State = T> + ?Sized,
+    {
+        let Message {
+            state,
+            cost,
+            parent,
+        } = message;
+
+        let priority = cost + space.heuristic(&state, goal);
+        if priority >= shared.incumbent() {
+            return;
+        }
+
+        match self.closed.entry(state.clone()) {
+            Entry::Occupied(entry) if entry.get().0 <= cost => return,
+            Entry::Occupied(mut entry) => {
+                entry.insert((cost, parent));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((cost, parent));
+            }
+        }
+
+        self.open.enqueue(priority, (state, cost.to_bits()));
+        if !self.active {
+            self.active = true;
+            shared.pending.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn expand<S>(&mut self, space: &S, goal: &T, shared: &Shared, state: &T, cost: f32)
+    where
+        S: HeuristicStateSpace<State = T> + ?Sized,
+    {
+        // A cheaper path to this state has been found since it was opened.
+        if self.closed[state].0 < cost {
+            return;
+        }
+        if cost + space.heuristic(state, goal) >= shared.incumbent() {
+            return;
+        }
+        if state == goal {
+            shared.improve_incumbent(cost);
+            return;
+        }
+
+        self.stats.expanded += 1;
+
+        let threads = self.senders.len();
+        let mut local = Vec::new();
+        space.for_each_neighbour(state, &mut |neighbour| {
+            self.stats.generated += 1;
+
+            let message = Message {
+                cost: cost + space.cost(state, &neighbour),
+                state: neighbour,
+                parent: Some(state.clone()),
+            };
+
+            let owner = shared.owner(&message.state, threads);
+            if owner == self.id {
+                local.push(message);
+            } else {
+                self.stats.sent += 1;
+                shared.pending.fetch_add(1, Ordering::SeqCst);
+                self.senders[owner].send(message).unwrap();
+            }
+        });
+
+        for message in local {
+            self.relax(space, goal, shared, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Grid;
+    use crate::StateSpace;
+
+    #[test]
+    fn optimal_on_every_thread_count() {
+        let grid = Grid::from_rows(&[
+            "..........",
+            ".XXXXXXXX.",
+            ".X......X.",
+            ".X.XXXX.X.",
+            ".X.X..X.X.",
+            ".X.X.XX.X.",
+            "...X......",
+        ]);
+
+        for threads in 1..=4 {
+            let result = hda_star(&grid, (0, 0), (4, 4), threads);
+            let path = result.search.path.unwrap();
+
+            assert_eq!(path.len(), grid.a_star((0, 0), (4, 4)).len());
+            assert_eq!(result.search.cost, Some(22.));
+            assert!(path
+                .windows(2)
+                .all(|w| grid.neighbours(&w[0]).contains(&w[1])));
+            assert_eq!(result.workers.len(), threads);
+        }
+    }
+
+    #[test]
+    fn exhausted_when_unreachable() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+
+        let result = hda_star(&grid, (0, 0), (3, 0), 1);
+        assert_eq!(result.search.termination, Termination::Exhausted);
+        assert_eq!(result.search.stats.expanded, 4);
+
+        let result = hda_star(&grid, (0, 0), (3, 0), 3);
+        assert_eq!(result.search.termination, Termination::Exhausted);
+        // A state may be expanded again when a cheaper path reaches its owner
+        // late, at most once per cost of a simple path to it: 1 for (0, 0)
+        // and (1, 1), 2 for (1, 0) and (0, 1).
+        assert!((4..=6).contains(&result.search.stats.expanded));
+    }
+
+    #[test]
+    fn load_imbalance() {
+        let result = HdaResult::<()> {
+            search: SearchResult {
+                path: None,
+                cost: None,
+                termination: Termination::Exhausted,
+                stats: SearchStats::default(),
+            },
+            workers: [30, 10, 20]
+                .iter()
+                .map(|&expanded| WorkerStats {
+                    expanded,
+                    ..WorkerStats::default()
+                })
+                .collect(),
+        };
+        assert!((result.load_imbalance() - 1.5).abs() < 1e-9);
+
+        let grid = Grid::open(20, 20);
+        let result = hda_star(&grid, (0, 0), (19, 19), 4);
+        let expanded: Vec<_> = result.workers.iter().map(|w| w.expanded).collect();
+
+        assert_eq!(expanded.iter().sum::<usize>(), result.search.stats.expanded);
+        // The states are spread by their hashes.
+        assert!(result.load_imbalance() < 1.5, "{:?}", expanded);
+    }
+}
@@ -0,0 +1,632 @@
+//! Pattern databases built from abstractions of state spaces.
+//!
+//! An [`Abstraction`] maps the states of a state space to the states of a
+//! smaller one, such that each transition maps to a transition costing no
+//! more, or to none. The cost of the cheapest abstract path to the goal is
+//! then a lower bound of the concrete one, and a [`PatternDatabase`] stores
+//! it for every abstract state, computed by a single backward search from
+//! the abstract goal.
+//!
+//! The distances are indexed by [`Abstraction::index`], taking a byte per
+//! abstract state when they are small integers, and the table is the only
+//! record of the states reached by the search. The database can be saved to
+//! disk to be built only once. [`WithPatternDatabase`] gives a
+//! state space its lookup as heuristic.
+//!
+//! ```
+//! # use state_space::grid::{CellState, Grid, Position};
+//! # use state_space::pattern_database::{Abstraction, PatternDatabase, WithPatternDatabase};
+//! # use state_space::{HeuristicStateSpace, StateSpace};
+//! /// The columns of a grid, forgetting the rows and the walls.
+//! struct Columns(usize);
+//!
+//! impl StateSpace for Columns {
+//!     type State = usize;
+//!
+//!     fn neighbours(&self, &x: &usize) -> Vec<usize> {
+//!         (x.saturating_sub(1)..(x + 2).min(self.0)).filter(|&n| n != x).collect()
+//!     }
+//! }
+//!
+//! impl Abstraction for Columns {
+//!     type Concrete = Position;
+//!
+//!     fn abstract_state(&self, &(x, _): &Position) -> usize { x }
+//!     fn size(&self) -> usize { self.0 }
+//!     fn index(&self, &x: &usize) -> usize { x }
+//! }
+//!
+//! let maze: Grid<CellState> = "  X  \n  X  \n     ".parse().unwrap();
+//! let database = PatternDatabase::breadth_first(Columns(maze.width()), &(4, 0));
+//! assert_eq!(database.lookup(&(0, 0)), 4.);
+//!
+//! let maze = WithPatternDatabase::new(maze, database);
+//! assert_eq!(maze.a_star((0, 0), (4, 0)).len(), 9);
+//! ```
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::priority_queue::MinPrioriyQueue;
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// The first bytes of a saved pattern database.
+const MAGIC: &[u8; 8] = b"STSPPDB1";
+
+/// A mapping of the states of a state space to abstract states, the
+/// abstraction being itself a state space.
+///
+/// The abstract states have to be numbered from 0 to [`Abstraction::size`],
+/// and the transitions of the abstraction have to include the images of the
+/// concrete ones, with costs no higher, for the distances in the abstraction
+/// to be lower bounds.
+pub trait Abstraction: StateSpace {
+    /// The type of the concrete states.
+    type Concrete;
+
+    /// Returns the abstract state of a concrete state.
+    fn abstract_state(&self, state: &Self::Concrete) -> Self::State;
+
+    /// Returns the number of abstract states.
+    fn size(&self) -> usize;
+
+    /// Returns the number of an abstract state, lower than
+    /// [`Abstraction::size`].
+    fn index(&self, state: &Self::State) -> usize;
+
+    /// Calls `f` on each of the abstract states having `state` as neighbour.
+    ///
+    /// It defaults to the neighbours of `state`, which suits abstractions
+    /// whose transitions can be reversed.
+    #[inline]
+    fn for_each_predecessor(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.for_each_neighbour(state, f);
+    }
+}
+
+/// The distances to the abstract goal of every abstract state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternDatabase<A> {
+    abstraction: A,
+    /// The index of the abstract goal.
+    goal: usize,
+    table: Table,
+}
+
+/// The distances indexed by the abstract states, stored in the narrowest of
+/// the widths representing them exactly.
+#[derive(Debug, Clone, PartialEq)]
+enum Table {
+    /// Integers below 255, the unreached states holding 255.
+    Narrow(Vec<u8>),
+    /// Integers below 65535, the unreached states holding 65535.
+    Wide(Vec<u16>),
+    /// Any distance, the unreached states holding infinity.
+    Real(Vec<f32>),
+}
+
+impl Table {
+    /// Creates the table of `size` unreached states.
+    fn unreached(size: usize) -> Self {
+        Self::Narrow(vec![u8::MAX; size])
+    }
+
+    /// Sets the distance of the state `index`, widening the table if it
+    /// cannot represent it.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn set(&mut self, index: usize, distance: f32) {
+        let fits = |max: u16| distance.fract() == 0. && distance < f32::from(max);
+
+        loop {
+            match self {
+                Self::Narrow(table) if fits(u16::from(u8::MAX)) => {
+                    table[index] = distance as u8;
+                    return;
+                }
+                Self::Wide(table) if fits(u16::MAX) => {
+                    table[index] = distance as u16;
+                    return;
+                }
+                Self::Real(table) => {
+                    table[index] = distance;
+                    return;
+                }
+                Self::Narrow(table) => {
+                    *self = Self::Wide(
+                        table
+                            .iter()
+                            .map(|&d| if d == u8::MAX { u16::MAX } else { u16::from(d) })
+                            .collect(),
+                    );
+                }
+                Self::Wide(table) => {
+                    *self = Self::Real(
+                        table
+                            .iter()
+                            .map(|&d| {
+                                if d == u16::MAX {
+                                    f32::INFINITY
+                                } else {
+                                    f32::from(d)
+                                }
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Narrow(table) => table.len(),
+            Self::Wide(table) => table.len(),
+            Self::Real(table) => table.len(),
+        }
+    }
+
+    fn get(&self, index: usize) -> f32 {
+        match self {
+            Self::Narrow(table) if table[index] == u8::MAX => f32::INFINITY,
+            Self::Narrow(table) => f32::from(table[index]),
+            Self::Wide(table) if table[index] == u16::MAX => f32::INFINITY,
+            Self::Wide(table) => f32::from(table[index]),
+            Self::Real(table) => table[index],
+        }
+    }
+
+    /// Returns the number of bytes of each distance.
+    const fn width(&self) -> u8 {
+        match self {
+            Self::Narrow(_) => 1,
+            Self::Wide(_) => 2,
+            Self::Real(_) => 4,
+        }
+    }
+}
+
+impl<A: Abstraction> PatternDatabase<A> {
+    /// Builds the database with a backward breadth-first search from the
+    /// abstraction of `goal`, every abstract transition costing 1.
+    ///
+    /// The distances are lower bounds only if the concrete transitions cost
+    /// at least 1.
+    #[must_use]
+    pub fn breadth_first(abstraction: A, goal: &A::Concrete) -> Self {
+        let goal = abstraction.abstract_state(goal);
+        let goal_index = abstraction.index(&goal);
+        let mut table = Table::unreached(abstraction.size());
+        table.set(goal_index, 0.);
+
+        let mut queue = VecDeque::new();
+        queue.push_back(goal);
+        while let Some(state) = queue.pop_front() {
+            let distance = table.get(abstraction.index(&state)) + 1.;
+            abstraction.for_each_predecessor(&state, &mut |predecessor| {
+                let index = abstraction.index(&predecessor);
+                if table.get(index).is_infinite() {
+                    table.set(index, distance);
+                    queue.push_back(predecessor);
+                }
+            });
+        }
+
+        Self {
+            abstraction,
+            goal: goal_index,
+            table,
+        }
+    }
+
+    /// Builds the database with a backward Dijkstra search from the
+    /// abstraction of `goal`.
+    #[must_use]
+    pub fn dijkstra(abstraction: A, goal: &A::Concrete) -> Self
+    where
+        A: CostStateSpace,
+    {
+        let goal = abstraction.abstract_state(goal);
+        let goal_index = abstraction.index(&goal);
+        let mut table = Table::unreached(abstraction.size());
+        table.set(goal_index, 0.);
+
+        // The states along with the bits of their distance.
+        let mut open = MinPrioriyQueue::new();
+        open.enqueue(0., (goal, 0_f32.to_bits()));
+        while let Some((state, distance)) = open.dequeue() {
+            let distance = f32::from_bits(distance);
+            // A shorter path to this state has been found since it was opened.
+            if table.get(abstraction.index(&state)) < distance {
+                continue;
+            }
+
+            abstraction.for_each_predecessor(&state, &mut |predecessor| {
+                let index = abstraction.index(&predecessor);
+                let cost = distance + abstraction.cost(&predecessor, &state);
+                if cost < table.get(index) {
+                    table.set(index, cost);
+                    open.enqueue(cost, (predecessor, cost.to_bits()));
+                }
+            });
+        }
+
+        Self {
+            abstraction,
+            goal: goal_index,
+            table,
+        }
+    }
+
+    /// Returns the abstraction.
+    #[inline]
+    #[must_use]
+    pub const fn abstraction(&self) -> &A {
+        &self.abstraction
+    }
+
+    /// Returns the number of bytes taken by the distances.
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        self.table.len() * usize::from(self.table.width())
+    }
+
+    /// Returns the distance from the abstraction of `state` to the abstract
+    /// goal, infinity if the goal cannot be reached from it.
+    #[inline]
+    #[must_use]
+    pub fn lookup(&self, state: &A::Concrete) -> f32 {
+        let state = self.abstraction.abstract_state(state);
+        self.table.get(self.abstraction.index(&state))
+    }
+
+    /// Returns a lower bound of the cost from `state` to `goal`, the
+    /// [`PatternDatabase::lookup`] of `state` if the database has been built
+    /// for the abstraction of `goal` and 0 otherwise.
+    #[must_use]
+    pub fn estimate(&self, state: &A::Concrete, goal: &A::Concrete) -> f32 {
+        let goal = self.abstraction.abstract_state(goal);
+        if self.abstraction.index(&goal) == self.goal {
+            self.lookup(state)
+        } else {
+            0.
+        }
+    }
+
+    /// Writes the distances to the file at `path`.
+    ///
+    /// They are first written next to it, with `.partial` appended to its
+    /// name, then moved, so that the file is either complete or missing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        // Appended rather than replacing the extension, so that `db.a` and
+        // `db.b` are written to different files.
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[self.table.width()])?;
+        writer.write_all(&(self.goal as u64).to_le_bytes())?;
+        writer.write_all(&(self.table.len() as u64).to_le_bytes())?;
+        match &self.table {
+            Table::Narrow(table) => writer.write_all(table)?,
+            Table::Wide(table) => {
+                for distance in table {
+                    writer.write_all(&distance.to_le_bytes())?;
+                }
+            }
+            Table::Real(table) => {
+                for distance in table {
+                    writer.write_all(&distance.to_le_bytes())?;
+                }
+            }
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        fs::rename(partial, path)
+    }
+
+    /// Reads the distances of `abstraction` from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or does not hold a
+    /// database of as many abstract states as `abstraction`.
+    pub fn load(abstraction: A, path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        let mut width = [0; 1];
+        reader.read_exact(&mut width)?;
+        let goal = read_u64(&mut reader)?;
+        let len = read_u64(&mut reader)?;
+        if &magic != MAGIC || len != abstraction.size() || goal >= len {
+            return Err(invalid_data("Not a pattern database of this abstraction"));
+        }
+
+        let table = match width[0] {
+            1 => {
+                let mut table = vec![0; len];
+                reader.read_exact(&mut table)?;
+                Table::Narrow(table)
+            }
+            2 => Table::Wide(read_values(&mut reader, len, u16::from_le_bytes)?),
+            4 => Table::Real(read_values(&mut reader, len, f32::from_le_bytes)?),
+            _ => return Err(invalid_data("Unknown width of distances")),
+        };
+
+        Ok(Self {
+            abstraction,
+            goal,
+            table,
+        })
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<usize> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| invalid_data("Database too large"))
+}
+
+fn read_values<T, const N: usize>(
+    reader: &mut impl Read,
+    len: usize,
+    from_bytes: fn([u8; N]) -> T,
+) -> io::Result<Vec<T>> {
+    (0..len)
+        .map(|_| {
+            let mut bytes = [0; N];
+            reader.read_exact(&mut bytes)?;
+            Ok(from_bytes(bytes))
+        })
+        .collect()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A state space whose heuristic is the [`PatternDatabase::estimate`] of a
+/// pattern database.
+#[derive(Debug, Clone)]
+pub struct WithPatternDatabase<S, A> {
+    space: S,
+    database: PatternDatabase<A>,
+}
+
+impl<S, A> WithPatternDatabase<S, A>
+where
+    S: StateSpace,
+    A: Abstraction<Concrete = S::State>,
+{
+    /// Gives `space` the heuristic of `database`.
+    #[inline]
+    #[must_use]
+    pub const fn new(space: S, database: PatternDatabase<A>) -> Self {
+        Self { space, database }
+    }
+
+    /// Returns the state space.
+    #[inline]
+    #[must_use]
+    pub const fn space(&self) -> &S {
+        &self.space
+    }
+
+    /// Returns the pattern database.
+    #[inline]
+    #[must_use]
+    pub const fn database(&self) -> &PatternDatabase<A> {
+        &self.database
+    }
+}
+
+impl<S, A> StateSpace for WithPatternDatabase<S, A>
+where
+    S: StateSpace,
+    A: Abstraction<Concrete = S::State>,
+{
+    type State = S::State;
+
+    #[inline]
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.space.for_each_neighbour(state, f);
+    }
+}
+
+impl<S, A> CostStateSpace for WithPatternDatabase<S, A>
+where
+    S: CostStateSpace,
+    A: Abstraction<Concrete = S::State>,
+{
+    #[inline]
+    fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+        self.space.cost(current, next)
+    }
+}
+
+impl<S, A> HeuristicStateSpace for WithPatternDatabase<S, A>
+where
+    S: CostStateSpace,
+    A: Abstraction<Concrete = S::State>,
+{
+    #[inline]
+    fn heuristic(&self, state: &Self::State, goal: &Self::State) -> f32 {
+        self.database.estimate(state, goal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sliding_puzzle::{Board, SlidingPuzzle};
+
+    /// The 8-puzzle where the tiles 5 to 8 cannot be told apart.
+    struct FirstTiles;
+
+    impl StateSpace for FirstTiles {
+        type State = Vec<u8>;
+
+        fn for_each_neighbour(&self, tiles: &Vec<u8>, f: &mut dyn FnMut(Vec<u8>)) {
+            let blank = tiles.iter().position(|&tile| tile == 0).unwrap();
+            let (x, y) = (blank % 3, blank / 3);
+            let adjacent = [
+                (y > 0, blank.wrapping_sub(3)),
+                (y < 2, blank + 3),
+                (x > 0, blank.wrapping_sub(1)),
+                (x < 2, blank + 1),
+            ];
+            for (exists, position) in adjacent.iter().copied() {
+                if exists {
+                    let mut next = tiles.clone();
+                    next.swap(blank, position);
+                    f(next);
+                }
+            }
+        }
+    }
+
+    impl Abstraction for FirstTiles {
+        type Concrete = Board;
+
+        fn abstract_state(&self, state: &Board) -> Vec<u8> {
+            state.tiles().iter().map(|&tile| tile.min(5)).collect()
+        }
+
+        fn size(&self) -> usize {
+            9_usize.pow(5)
+        }
+
+        fn index(&self, tiles: &Vec<u8>) -> usize {
+            (0..5).fold(0, |index, tile| {
+                let position = tiles.iter().position(|&t| t == tile).unwrap();
+                index * 9 + position
+            })
+        }
+    }
+
+    /// A directed cycle of `n` states, going forward costing 1.5.
+    #[derive(Debug, PartialEq)]
+    struct Cycle(usize);
+
+    impl StateSpace for Cycle {
+        type State = usize;
+
+        fn neighbours(&self, &state: &usize) -> Vec<usize> {
+            vec![(state + 1) % self.0]
+        }
+    }
+
+    impl CostStateSpace for Cycle {
+        fn cost(&self, _: &usize, _: &usize) -> f32 {
+            1.5
+        }
+    }
+
+    impl Abstraction for Cycle {
+        type Concrete = usize;
+
+        fn abstract_state(&self, &state: &usize) -> usize {
+            state
+        }
+
+        fn size(&self) -> usize {
+            self.0
+        }
+
+        fn index(&self, &state: &usize) -> usize {
+            state
+        }
+
+        fn for_each_predecessor(&self, &state: &usize, f: &mut dyn FnMut(usize)) {
+            f((state + self.0 - 1) % self.0);
+        }
+    }
+
+    #[test]
+    fn guides_a_star_optimally() {
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let goal = puzzle.goal();
+        let database = PatternDatabase::breadth_first(FirstTiles, &goal);
+        assert_eq!(database.bytes(), 9_usize.pow(5));
+
+        let board = Board::new(vec![8, 6, 7, 2, 5, 4, 3, 0, 1]);
+        let moves = database.lookup(&board);
+        assert!(moves > 0. && moves <= 31.);
+
+        let guided = WithPatternDatabase::new(puzzle, database);
+        assert_eq!(guided.a_star(board, goal.clone()).len(), 32);
+        assert!(guided.heuristic(&goal, &goal).abs() < 1e-6);
+    }
+
+    #[test]
+    fn searches_backward_with_costs() {
+        let database = PatternDatabase::dijkstra(Cycle(5), &0);
+
+        assert!((database.lookup(&1) - 6.).abs() < 1e-6);
+        assert!((database.lookup(&4) - 1.5).abs() < 1e-6);
+        assert_eq!(database.bytes(), 5 * 4);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = std::env::temp_dir().join(format!("pdb-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let wide = PatternDatabase::breadth_first(Cycle(300), &0);
+        let real = PatternDatabase::dijkstra(Cycle(5), &0);
+        wide.save(dir.join("wide")).unwrap();
+        real.save(dir.join("real")).unwrap();
+
+        assert_eq!(wide.bytes(), 600);
+        assert_eq!(
+            PatternDatabase::load(Cycle(300), dir.join("wide")).unwrap(),
+            wide
+        );
+        assert_eq!(
+            PatternDatabase::load(Cycle(5), dir.join("real")).unwrap(),
+            real
+        );
+        assert_eq!(
+            PatternDatabase::load(Cycle(6), dir.join("real"))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saves_files_differing_in_extension() {
+        let dir = std::env::temp_dir().join(format!("pdb-extension-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let a = PatternDatabase::breadth_first(Cycle(4), &0);
+        let b = PatternDatabase::breadth_first(Cycle(6), &0);
+        a.save(dir.join("db.a")).unwrap();
+        b.save(dir.join("db.b")).unwrap();
+
+        assert_eq!(
+            PatternDatabase::load(Cycle(4), dir.join("db.a")).unwrap(),
+            a
+        );
+        assert_eq!(
+            PatternDatabase::load(Cycle(6), dir.join("db.b")).unwrap(),
+            b
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
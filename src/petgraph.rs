@@ -0,0 +1,249 @@
+//! Interoperability with [petgraph](https://docs.rs/petgraph).
+//!
+//! The graphs of petgraph are state spaces whose states are their nodes,
+//! [`NodeIndex`] for [`Graph`] and [`StableGraph`] and the node weights for
+//! [`GraphMap`]. The cost of a transition is the weight of the cheapest edge
+//! between the two nodes, converted with [`Into<f32>`].
+//!
+//! Conversely, [`to_graph`] and [`to_cost_graph`] export a region of a state
+//! space, explored for instance with
+//! [`StateSpace::distances_from`](crate::StateSpace::distances_from), to a
+//! [`Graph`] to analyse it with the algorithms of petgraph.
+//!
+//! ```
+//! # use petgraph::graph::DiGraph;
+//! # use state_space::{CostStateSpace, StateSpace};
+//! let mut graph = DiGraph::<&str, f32>::new();
+//! let home = graph.add_node("home");
+//! let station = graph.add_node("station");
+//! let office = graph.add_node("office");
+//! graph.extend_with_edges(&[(home, station, 5.), (station, office, 20.), (home, office, 40.)]);
+//!
+//! assert_eq!(graph.dijkstra(home, office), vec![home, station, office]);
+//! ```
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use ::petgraph::graph::{Graph, IndexType, NodeIndex};
+use ::petgraph::graphmap::{GraphMap, NodeTrait};
+use ::petgraph::stable_graph::StableGraph;
+use ::petgraph::EdgeType;
+
+use crate::distance::DistanceMap;
+use crate::{CostStateSpace, StateSpace};
+
+impl<N, E, Ty, Ix> StateSpace for Graph<N, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type State = NodeIndex<Ix>;
+
+    #[inline]
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.neighbors(*state).for_each(f);
+    }
+}
+
+impl<N, E, Ty, Ix> CostStateSpace for Graph<N, E, Ty, Ix>
+where
+    E: Copy + Into<f32>,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    #[inline]
+    fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+        self.edges_connecting(*current, *next)
+            .map(|edge| (*edge.weight()).into())
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+impl<N, E, Ty, Ix> StateSpace for StableGraph<N, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type State = NodeIndex<Ix>;
+
+    #[inline]
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.neighbors(*state).for_each(f);
+    }
+}
+
+impl<N, E, Ty, Ix> CostStateSpace for StableGraph<N, E, Ty, Ix>
+where
+    E: Copy + Into<f32>,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    #[inline]
+    fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+        self.edges_connecting(*current, *next)
+            .map(|edge| (*edge.weight()).into())
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+impl<N, E, Ty, H> StateSpace for GraphMap<N, E, Ty, H>
+where
+    N: NodeTrait,
+    Ty: EdgeType,
+    H: BuildHasher,
+{
+    type State = N;
+
+    #[inline]
+    fn for_each_neighbour(&self, state: &Self::State, f: &mut dyn FnMut(Self::State)) {
+        self.neighbors(*state).for_each(f);
+    }
+}
+
+impl<N, E, Ty, H> CostStateSpace for GraphMap<N, E, Ty, H>
+where
+    N: NodeTrait,
+    E: Copy + Into<f32>,
+    Ty: EdgeType,
+    H: BuildHasher,
+{
+    #[inline]
+    fn cost(&self, current: &Self::State, next: &Self::State) -> f32 {
+        self.edge_weight(*current, *next)
+            .map_or(f32::INFINITY, |&weight| weight.into())
+    }
+}
+
+/// Returns the graph of the states of `region` and of the transitions between
+/// them, the transitions leaving the region being left out.
+///
+/// ```
+/// # use state_space::petgraph::to_graph;
+/// # use state_space::StateSpace;
+/// # struct Line;
+/// # impl StateSpace for Line {
+/// #     type State = i32;
+/// #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+/// # }
+/// let region = Line.distances_from(0, Some(2));
+/// let graph = to_graph(&Line, &region);
+///
+/// assert_eq!(graph.node_count(), 5);
+/// assert_eq!(graph.edge_count(), 8);
+/// assert!(petgraph::algo::is_cyclic_directed(&graph));
+/// ```
+pub fn to_graph<S, H>(space: &S, region: &DistanceMap<S::State, H>) -> Graph<S::State, ()>
+where
+    S: StateSpace + ?Sized,
+{
+    export(space, region, |_, _| ())
+}
+
+/// Returns the graph of the states of `region` and of the transitions between
+/// them, weighted by their costs, as [`to_graph`].
+pub fn to_cost_graph<S, H>(space: &S, region: &DistanceMap<S::State, H>) -> Graph<S::State, f32>
+where
+    S: CostStateSpace + ?Sized,
+{
+    export(space, region, |current, next| space.cost(current, next))
+}
+
+fn export<S, H, E>(
+    space: &S,
+    region: &DistanceMap<S::State, H>,
+    mut weight: impl FnMut(&S::State, &S::State) -> E,
+) -> Graph<S::State, E>
+where
+    S: StateSpace + ?Sized,
+{
+    let mut graph = Graph::with_capacity(region.len(), region.len());
+    let nodes: HashMap<_, _> = region
+        .iter()
+        .map(|(state, _, _)| (state, graph.add_node(state.clone())))
+        .collect();
+
+    for (&state, &from) in &nodes {
+        space.for_each_neighbour(state, &mut |neighbour| {
+            if let Some(&to) = nodes.get(&neighbour) {
+                let weight = weight(state, &neighbour);
+                graph.add_edge(from, to, weight);
+            }
+        });
+    }
+
+    graph
+}
+
+/// Returns the node of `graph` whose weight is `state`, as exported by
+/// [`to_graph`] or [`to_cost_graph`].
+pub fn node_of<T: PartialEq, E>(graph: &Graph<T, E>, state: &T) -> Option<NodeIndex> {
+    graph.node_indices().find(|&node| &graph[node] == state)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::petgraph::algo::{dijkstra, kosaraju_scc};
+    use ::petgraph::graph::UnGraph;
+    use ::petgraph::graphmap::DiGraphMap;
+    use ::petgraph::stable_graph::StableDiGraph;
+
+    use super::*;
+    use crate::test_utils::Grid;
+
+    #[test]
+    fn graphs_are_state_spaces() {
+        let mut graph = UnGraph::<(), u8>::new_undirected();
+        let nodes: Vec<_> = (0..4).map(|_| graph.add_node(())).collect();
+        graph.extend_with_edges([
+            (nodes[0], nodes[1], 4),
+            (nodes[0], nodes[2], 1),
+            (nodes[2], nodes[1], 1),
+            (nodes[1], nodes[3], 1),
+            (nodes[0], nodes[1], 3),
+        ]);
+
+        assert!((graph.cost(&nodes[1], &nodes[0]) - 3.).abs() < 1e-6);
+        assert_eq!(
+            graph.dijkstra(nodes[3], nodes[0]),
+            vec![nodes[3], nodes[1], nodes[2], nodes[0]]
+        );
+        assert_eq!(
+            graph.breadth_first_search(nodes[3], nodes[0]),
+            vec![nodes[3], nodes[1], nodes[0]]
+        );
+
+        let mut stable = StableDiGraph::<(), f32>::new();
+        let a = stable.add_node(());
+        let b = stable.add_node(());
+        let c = stable.add_node(());
+        stable.add_edge(a, b, 1.);
+        stable.add_edge(b, c, 1.);
+        stable.remove_node(b);
+        assert_eq!(stable.search().run(a, c).path, None);
+
+        let map =
+            DiGraphMap::<char, f32>::from_edges(&[('a', 'b', 2.), ('b', 'c', 2.), ('a', 'c', 5.)]);
+        assert_eq!(map.dijkstra('a', 'c'), vec!['a', 'b', 'c']);
+        assert!(map.cost(&'c', &'a').is_infinite());
+    }
+
+    #[test]
+    fn export_explored_region() {
+        let grid = Grid::from_rows(&["...", ".X.", "..."]);
+        let region = grid.distances_from((0, 0), None);
+        let graph = to_cost_graph(&grid, &region);
+
+        assert_eq!(graph.node_count(), 8);
+        assert_eq!(graph.edge_count(), 16);
+        assert_eq!(kosaraju_scc(&graph).len(), 1);
+
+        let from = node_of(&graph, &(0, 0)).unwrap();
+        let to = node_of(&graph, &(2, 2)).unwrap();
+        let costs = dijkstra(&graph, from, Some(to), |edge| *edge.weight());
+        assert_eq!(costs.get(&to), Some(&4.));
+
+        let bounded = to_graph(&grid, &grid.distances_from((0, 0), Some(1)));
+        assert_eq!(bounded.node_count(), 3);
+        assert_eq!(bounded.edge_count(), 4);
+    }
+}
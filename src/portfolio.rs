@@ -0,0 +1,333 @@
+//! Several searches racing on the same state space.
+//!
+//! A [`Portfolio`] runs each of its algorithms on its own thread and returns
+//! the first solution found, or the cheapest one found before a deadline,
+//! cancelling the searches still running.
+//!
+//! ```
+//! # use state_space::{CostStateSpace, HeuristicStateSpace, StateSpace};
+//! # use state_space::algorithm::{AStar, BreadthFirstSearch, GreedySearch, WeightedAStar};
+//! # use state_space::portfolio::Portfolio;
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = i32;
+//! #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+//! # }
+//! # impl CostStateSpace for Line {
+//! #     fn cost(&self, _: &i32, _: &i32) -> f32 { 1. }
+//! # }
+//! # impl HeuristicStateSpace for Line {
+//! #     fn heuristic(&self, state: &i32, goal: &i32) -> f32 { (goal - state).abs() as f32 }
+//! # }
+//! let result = Portfolio::new(&Line)
+//!     .algorithm("a_star", AStar)
+//!     .algorithm("weighted_a_star", WeightedAStar(2.))
+//!     .algorithm("greedy_search", GreedySearch)
+//!     .algorithm("breadth_first_search", BreadthFirstSearch)
+//!     .run(0, 3);
+//!
+//! assert_eq!(result.into_path(), Some(vec![0, 1, 2, 3]));
+//! ```
+use std::collections::hash_map::RandomState;
+use std::slice;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::SearchAlgorithm;
+use crate::engine::{self, Config};
+use crate::search::{
+    CancellationToken, DuplicateDetection, Limits, NoObserver, SearchResult, TieBreak,
+};
+use crate::CostStateSpace;
+
+/// Which solution a [`Portfolio`] returns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PortfolioMode {
+    /// The first solution found, the other searches being cancelled as soon
+    /// as it is.
+    First,
+    /// The cheapest solution found once every search has stopped or the
+    /// deadline has passed.
+    Best,
+}
+
+impl Default for PortfolioMode {
+    #[inline]
+    fn default() -> Self {
+        Self::First
+    }
+}
+
+/// The outcome of one of the searches of a [`Portfolio`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioRun<T> {
+    /// The name given to the algorithm.
+    pub name: String,
+    /// The outcome of the search, the cost of its path being measured with
+    /// [`CostStateSpace::cost`] whatever the algorithm.
+    pub result: SearchResult<T>,
+    /// The time taken by the search.
+    pub elapsed: Duration,
+}
+
+/// The outcome of a [`Portfolio`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioResult<T> {
+    /// The index in [`PortfolioResult::runs`] of the search whose solution is
+    /// returned, if any found one.
+    pub winner: Option<usize>,
+    /// The outcome of each search, in the order the algorithms were added.
+    pub runs: Vec<PortfolioRun<T>>,
+}
+
+impl<T> PortfolioResult<T> {
+    /// Returns the run whose solution is returned, if any.
+    #[inline]
+    #[must_use]
+    pub fn winner(&self) -> Option<&PortfolioRun<T>> {
+        self.winner.map(|index| &self.runs[index])
+    }
+
+    /// Returns the path of the winning run, if any.
+    #[inline]
+    #[must_use]
+    pub fn into_path(mut self) -> Option<Vec<T>> {
+        let index = self.winner?;
+        self.runs.swap_remove(index).result.path
+    }
+}
+
+type BoxedAlgorithm<'a, S> = Box<dyn SearchAlgorithm<S> + Send + 'a>;
+
+/// A set of algorithms run in parallel on the same state space.
+#[must_use]
+pub struct Portfolio<'a, S: CostStateSpace + ?Sized> {
+    space: &'a S,
+    algorithms: Vec<(String, BoxedAlgorithm<'a, S>)>,
+    mode: PortfolioMode,
+    deadline: Option<Duration>,
+    limits: Limits,
+}
+
+impl<'a, S: CostStateSpace + ?Sized> Portfolio<'a, S> {
+    /// Creates an empty portfolio over `space`, returning the first solution
+    /// found without deadline.
+    #[inline]
+    pub fn new(space: &'a S) -> Self {
+        Self {
+            space,
+            algorithms: Vec::new(),
+            mode: PortfolioMode::First,
+            deadline: None,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Adds `algorithm` to the portfolio under `name`.
+    pub fn algorithm<A>(mut self, name: impl Into<String>, algorithm: A) -> Self
+    where
+        A: SearchAlgorithm<S> + Send + 'a,
+    {
+        self.algorithms.push((name.into(), Box::new(algorithm)));
+        self
+    }
+
+    /// Sets which solution is returned, [`PortfolioMode::First`] by default.
+    #[inline]
+    pub const fn mode(mut self, mode: PortfolioMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Cancels the searches still running after `deadline`.
+    #[inline]
+    pub const fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the limits of each search.
+    #[inline]
+    pub const fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl<S> Portfolio<'_, S>
+where
+    S: CostStateSpace + Sync + ?Sized,
+    S::State: Send,
+{
+    /// Runs every algorithm from `init` to `goal`, each on its own thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the searches panics.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn run(self, init: S::State, goal: S::State) -> PortfolioResult<S::State> {
+        let start = Instant::now();
+        let token = CancellationToken::new();
+        let config = Config {
+            limits: self.limits,
+            tie_break: TieBreak::Fifo,
+            duplicate_detection: DuplicateDetection::ClosedSet,
+            cancellation: Some(token.clone()),
+            check_costs: false,
+        };
+        let space = self.space;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut runs: Vec<Option<PortfolioRun<S::State>>> = Vec::new();
+        let mut winner = None;
+
+        thread::scope(|scope| {
+            for (index, (name, algorithm)) in self.algorithms.into_iter().enumerate() {
+                let sender = sender.clone();
+                let config = config.clone();
+                let init = init.clone();
+                let goal = goal.clone();
+
+                runs.push(None);
+                scope.spawn(move || {
+                    let mut result = engine::search(
+                        space,
+                        &*algorithm,
+                        &mut NoObserver,
+                        &config,
+                        RandomState::new(),
+                        init,
+                        slice::from_ref(&goal),
+                    );
+                    result.cost = result.path.as_ref().map(|path| path_cost(space, path));
+
+                    let run = PortfolioRun {
+                        name,
+                        result,
+                        elapsed: start.elapsed(),
+                    };
+                    // The receiver outlives the scope.
+                    sender.send((index, run)).unwrap();
+                });
+            }
+            drop(sender);
+
+            loop {
+                let message = match self.deadline {
+                    Some(deadline) if !token.is_cancelled() => {
+                        match receiver.recv_timeout(deadline.saturating_sub(start.elapsed())) {
+                            Ok(message) => Some(message),
+                            Err(mpsc::RecvTimeoutError::Timeout) => {
+                                token.cancel();
+                                continue;
+                            }
+                            Err(mpsc::RecvTimeoutError::Disconnected) => None,
+                        }
+                    }
+                    _ => receiver.recv().ok(),
+                };
+                let Some((index, run)) = message else {
+                    break;
+                };
+
+                if let Some(cost) = run.result.cost {
+                    let better = match self.mode {
+                        PortfolioMode::First => winner.is_none(),
+                        PortfolioMode::Best => winner.is_none_or(|(_, best)| cost < best),
+                    };
+                    if better {
+                        winner = Some((index, cost));
+                    }
+                    if self.mode == PortfolioMode::First {
+                        token.cancel();
+                    }
+                }
+                runs[index] = Some(run);
+            }
+        });
+
+        PortfolioResult {
+            winner: winner.map(|(index, _)| index),
+            runs: runs
+                .into_iter()
+                .map(|run| run.expect("Every search should report its outcome"))
+                .collect(),
+        }
+    }
+}
+
+fn path_cost<S: CostStateSpace + ?Sized>(space: &S, path: &[S::State]) -> f32 {
+    path.windows(2).map(|w| space.cost(&w[0], &w[1])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{AStar, BreadthFirstSearch, DepthFirstSearch, GreedySearch};
+    use crate::search::Termination;
+    use crate::test_utils::Grid;
+    use crate::StateSpace;
+
+    /// A state space whose states never run out, to be cancelled.
+    struct Endless;
+
+    impl StateSpace for Endless {
+        type State = u64;
+
+        fn neighbours(&self, state: &u64) -> Vec<u64> {
+            vec![state + 1]
+        }
+    }
+
+    impl CostStateSpace for Endless {
+        fn cost(&self, _current: &u64, _next: &u64) -> f32 {
+            1.
+        }
+    }
+
+    #[test]
+    fn first_solution_cancels_the_rest() {
+        let grid = Grid::open(100, 100);
+        let result = Portfolio::new(&grid)
+            .algorithm("greedy_search", GreedySearch)
+            .algorithm("breadth_first_search", BreadthFirstSearch)
+            .run((0, 0), (99, 99));
+
+        assert!(result.winner().unwrap().result.is_found());
+        assert!(result.runs.iter().all(|run| matches!(
+            run.result.termination,
+            Termination::Found | Termination::Cancelled
+        )));
+    }
+
+    #[test]
+    fn best_solution_within_deadline() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+        let result = Portfolio::new(&grid)
+            .mode(PortfolioMode::Best)
+            .algorithm("depth_first_search", DepthFirstSearch)
+            .algorithm("breadth_first_search", BreadthFirstSearch)
+            .algorithm("a_star", AStar)
+            .deadline(Duration::from_secs(10))
+            .run((0, 0), (3, 3));
+
+        assert_eq!(result.winner().unwrap().result.cost, Some(6.));
+        assert!(result.runs.iter().all(|run| run.result.is_found()));
+    }
+
+    #[test]
+    fn deadline_cancels_endless_searches() {
+        let result = Portfolio::new(&Endless)
+            .algorithm("breadth_first_search", BreadthFirstSearch)
+            .algorithm("depth_first_search", DepthFirstSearch)
+            .deadline(Duration::from_millis(20))
+            .run(0, u64::MAX);
+
+        assert_eq!(result.winner, None);
+        assert!(result
+            .runs
+            .iter()
+            .all(|run| run.result.termination == Termination::Cancelled));
+    }
+}
@@ -4,21 +4,49 @@ use std::collections::BinaryHeap;
 #[derive(Clone)]
 pub struct MinPrioriyQueue<T> {
     heap: BinaryHeap<InvertedPriority<T>>,
+    sequence: u64,
+    lifo: bool,
 }
 
 impl<T: Eq> MinPrioriyQueue<T> {
     /// Creates a new empty `MinPrioriyQueue<P, E>`.
     #[inline]
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
+        Self::with_insertion_order(false)
+    }
+
+    /// Creates a new empty `MinPrioriyQueue<P, E>` where elements with equal
+    /// priorities are dequeued in insertion order, or in reverse insertion
+    /// order if `lifo` is set.
+    #[inline]
+    pub const fn with_insertion_order(lifo: bool) -> Self {
         Self {
             heap: BinaryHeap::new(),
+            sequence: 0,
+            lifo,
         }
     }
 
     #[inline]
     pub fn enqueue(&mut self, priority: f32, element: T) {
-        self.heap
-            .push(InvertedPriority::new(priority, element).expect("Priority should not be NaN"));
+        self.enqueue_with_tie(priority, 0., element);
+    }
+
+    /// Enqueues an element, elements with equal `priority` being ordered by
+    /// lowest `tie` first.
+    #[inline]
+    pub fn enqueue_with_tie(&mut self, priority: f32, tie: f32, element: T) {
+        let sequence = if self.lifo {
+            u64::MAX - self.sequence
+        } else {
+            self.sequence
+        };
+        self.sequence += 1;
+
+        self.heap.push(
+            InvertedPriority::new(priority, tie, sequence, element)
+                .expect("Priority should not be NaN"),
+        );
     }
 
     #[inline]
@@ -37,6 +65,32 @@ impl<T> MinPrioriyQueue<T> {
     pub fn is_empty(&self) -> bool {
         self.heap.is_empty()
     }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.heap.iter().map(|e| &e.element)
+    }
+
+    /// Returns the elements along with their priorities, in insertion order.
+    #[cfg(feature = "serde")]
+    pub fn entries(&self) -> Vec<(f32, &T)> {
+        let mut entries: Vec<_> = self.heap.iter().collect();
+        if self.lifo {
+            entries.sort_unstable_by_key(|e| std::cmp::Reverse(e.sequence));
+        } else {
+            entries.sort_unstable_by_key(|e| e.sequence);
+        }
+
+        entries
+            .into_iter()
+            .map(|e| (e.priority.0, &e.element))
+            .collect()
+    }
 }
 
 impl<T> From<MinPrioriyQueue<T>> for Vec<T> {
@@ -74,42 +128,66 @@ impl Eq for NotNan {}
 impl Ord for NotNan {
     #[inline]
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("Value should not be NaN")
+        self.0
+            .partial_cmp(&other.0)
+            .expect("Value should not be NaN")
     }
 }
 
 impl PartialOrd for NotNan {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone)]
 struct InvertedPriority<T> {
     priority: NotNan,
+    tie: NotNan,
+    sequence: u64,
     element: T,
 }
 
 impl<T> InvertedPriority<T> {
     #[inline]
-    fn new(priority: f32, element: T) -> Option<Self> {
+    fn new(priority: f32, tie: f32, sequence: u64, element: T) -> Option<Self> {
         let priority = NotNan::new(priority)?;
+        let tie = NotNan::new(tie)?;
+
+        Some(Self {
+            priority,
+            tie,
+            sequence,
+            element,
+        })
+    }
 
-        Some(Self { priority, element })
+    #[inline]
+    const fn key(&self) -> (NotNan, NotNan, u64) {
+        (self.priority, self.tie, self.sequence)
+    }
+}
+
+impl<T> PartialEq for InvertedPriority<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
     }
 }
 
-impl<T: Eq> Ord for InvertedPriority<T> {
+impl<T> Eq for InvertedPriority<T> {}
+
+impl<T> Ord for InvertedPriority<T> {
     #[inline]
     fn cmp(&self, other: &Self) -> Ordering {
-        other.priority.cmp(&self.priority)
+        other.key().cmp(&self.key())
     }
 }
 
-impl<T: PartialEq> PartialOrd for InvertedPriority<T> {
+impl<T> PartialOrd for InvertedPriority<T> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.priority.partial_cmp(&self.priority)
+        Some(self.cmp(other))
     }
 }
@@ -0,0 +1,990 @@
+//! A configurable entry point to the searches of the crate.
+//!
+//! ```
+//! # use state_space::StateSpace;
+//! # use state_space::algorithm::BreadthFirstSearch;
+//! # use state_space::search::Limits;
+//! # struct Line;
+//! # impl StateSpace for Line {
+//! #     type State = i32;
+//! #     fn neighbours(&self, state: &i32) -> Vec<i32> { vec![state - 1, state + 1] }
+//! # }
+//! let result = Line
+//!     .search()
+//!     .algorithm(BreadthFirstSearch)
+//!     .limits(Limits {
+//!         max_expansions: Some(100),
+//!         ..Limits::default()
+//!     })
+//!     .run(0, 3);
+//!
+//! assert_eq!(result.path, Some(vec![0, 1, 2, 3]));
+//! ```
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::algorithm::{BreadthFirstSearch, SearchAlgorithm};
+#[cfg(feature = "serde")]
+use crate::checkpoint::Checkpoint;
+use crate::distance::DistanceMap;
+use crate::engine::{self, Config, Frontier, Node};
+use crate::visited::Visited;
+use crate::StateSpace;
+
+/// Bounds on the work done by a search.
+///
+/// A search reaching one of its limits stops without a solution and reports
+/// the limit in [`SearchResult::termination`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Limits {
+    /// The maximum number of states expanded.
+    pub max_expansions: Option<usize>,
+    /// The maximum depth of the expanded states.
+    pub max_depth: Option<usize>,
+    /// The maximum cost of the generated states.
+    pub max_cost: Option<f32>,
+    /// The maximum duration of the search.
+    pub time_limit: Option<Duration>,
+}
+
+/// A flag shared with running searches to stop them from another thread.
+///
+/// A search given a clone of the token with
+/// [`SearchBuilder::cancellation`] stops before its next expansion once the
+/// token is cancelled, reporting [`Termination::Cancelled`].
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token which is not cancelled.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the searches holding a clone of the token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the token has been cancelled.
+    #[inline]
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The order in which open states with equal priorities are expanded.
+///
+/// It only applies to algorithms expanding states by priority.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The first generated state is expanded first.
+    Fifo,
+    /// The last generated state is expanded first.
+    Lifo,
+    /// The state with the lowest cost is expanded first.
+    LowestCost,
+    /// The state with the highest cost is expanded first.
+    HighestCost,
+}
+
+impl Default for TieBreak {
+    #[inline]
+    fn default() -> Self {
+        Self::Fifo
+    }
+}
+
+/// How a search detects states it has already reached.
+///
+/// The algorithms reopening states reached through a cheaper path only do so
+/// with [`DuplicateDetection::ClosedSet`] and
+/// [`DuplicateDetection::Transposition`].
+///
+/// The probabilistic strategies, [`DuplicateDetection::Bitstate`],
+/// [`DuplicateDetection::Bloom`] and [`DuplicateDetection::HashCompaction`],
+/// only store hashes of the states and may wrongly consider a new state as
+/// already reached, omitting it and its successors. The estimated probability
+/// of such an omission is reported in [`SearchStats::omission_probability`].
+///
+/// Every strategy but [`DuplicateDetection::ClosedSet`] frees the states
+/// leading to no open state, so that a depth-first search stores O(bm)
+/// states.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DuplicateDetection {
+    /// Every successor is opened, which suits tree-shaped state spaces.
+    None,
+    /// Successors equal to the expanded state or its parent are discarded.
+    Parent,
+    /// Successors already on the path from the initial state are discarded.
+    Path,
+    /// Every state reached is remembered.
+    ClosedSet,
+    /// Up to the given number of the most recently reached states are
+    /// remembered.
+    Transposition(usize),
+    /// A bit per state is set in a table of `2^log2_bits` bits, as SPIN's
+    /// bitstate hashing.
+    ///
+    /// The search panics if `log2_bits` is not less than the number of bits
+    /// of `usize`.
+    Bitstate {
+        /// The base 2 logarithm of the size of the table.
+        log2_bits: u32,
+    },
+    /// The states are stored in a Bloom filter.
+    Bloom {
+        /// The number of bits of the filter.
+        bits: usize,
+        /// The number of bits set per state.
+        hashes: u32,
+    },
+    /// A 64-bit hash per state is stored.
+    HashCompaction,
+}
+
+impl Default for DuplicateDetection {
+    #[inline]
+    fn default() -> Self {
+        Self::ClosedSet
+    }
+}
+
+/// The reason a search stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Termination {
+    /// The goal has been reached.
+    Found,
+    /// Every reachable state has been expanded without reaching the goal.
+    Exhausted,
+    /// [`Limits::max_expansions`] has been reached.
+    ExpansionLimit,
+    /// States deeper than [`Limits::max_depth`] have been pruned.
+    DepthLimit,
+    /// States costlier than [`Limits::max_cost`] have been pruned.
+    CostLimit,
+    /// [`Limits::time_limit`] has been reached.
+    TimeLimit,
+    /// The [`CancellationToken`] of the search has been cancelled.
+    Cancelled,
+}
+
+/// Statistics collected during a search.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// The number of states expanded.
+    pub expanded: usize,
+    /// The number of successors generated.
+    pub generated: usize,
+    /// The largest size reached by the open list.
+    pub max_open: usize,
+    /// The estimated probability that a reachable state has been omitted,
+    /// with a probabilistic [`DuplicateDetection`].
+    pub omission_probability: Option<f64>,
+}
+
+/// The outcome of a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult<T> {
+    /// The path from the initial state to the goal, if found.
+    pub path: Option<Vec<T>>,
+    /// The cost of the path as measured by the algorithm, uninformed
+    /// algorithms counting one per transition.
+    pub cost: Option<f32>,
+    /// The reason the search stopped.
+    pub termination: Termination,
+    /// Statistics about the search.
+    pub stats: SearchStats,
+}
+
+impl<T> SearchResult<T> {
+    /// Returns whether the goal has been reached.
+    #[inline]
+    #[must_use]
+    pub const fn is_found(&self) -> bool {
+        matches!(self.termination, Termination::Found)
+    }
+
+    /// Returns the path to the goal, if found.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_path(self) -> Option<Vec<T>> {
+        self.path
+    }
+}
+
+/// The outcome of a search from several sources to several goals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiSearchResult<T> {
+    /// The index of the source the path starts from, if found.
+    pub source: Option<usize>,
+    /// The index of the goal the path leads to, if found.
+    pub goal: Option<usize>,
+    /// The outcome of the search, whose cost includes the offset of the
+    /// source.
+    pub search: SearchResult<T>,
+}
+
+/// A snapshot of a search given to an [`Observer`] after each expansion.
+pub struct Progress<'a, T> {
+    /// The initial state of the search, the cheapest one if it has several.
+    pub init: &'a T,
+    /// The goal of the search, the first one if it has several.
+    pub goal: Option<&'a T>,
+    /// The goals of the search, empty when exploring.
+    pub goals: &'a [T],
+    /// The state which has just been expanded.
+    pub current: &'a T,
+    /// The cost of the path to `current`.
+    pub cost: f32,
+    /// The depth of `current`.
+    pub depth: usize,
+    /// The statistics of the search so far.
+    pub stats: &'a SearchStats,
+    pub(crate) nodes: &'a [Node],
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) free: &'a [usize],
+    pub(crate) states: &'a [T],
+    pub(crate) frontier: &'a Frontier,
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) visited: &'a Visited<T>,
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) pruned: Option<Termination>,
+}
+
+impl<T: Clone> Progress<'_, T> {
+    /// Returns the states of the open list.
+    #[must_use]
+    pub fn open(&self) -> Vec<T> {
+        self.frontier
+            .iter()
+            .map(|index| self.states[self.nodes[index].state.index()].clone())
+            .collect()
+    }
+}
+
+impl<T> Progress<'_, T> {
+    /// Returns the number of states in the open list.
+    #[inline]
+    #[must_use]
+    pub fn open_len(&self) -> usize {
+        self.frontier.len()
+    }
+}
+
+/// A hook called as the search progresses.
+///
+/// It is implemented for closures taking a [`Progress`].
+pub trait Observer<S: StateSpace + ?Sized> {
+    /// Called after each expansion.
+    fn on_expand(&mut self, space: &S, progress: &Progress<'_, S::State>);
+}
+
+/// An observer forwarding the progress to [`StateSpace::display_progress`].
+///
+/// The open list is collected into a `Vec` after each expansion, which costs
+/// time proportional to its length. Nothing is displayed when exploring, the
+/// search having no goal.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DisplayProgress;
+
+impl<S: StateSpace + ?Sized> Observer<S> for DisplayProgress {
+    #[inline]
+    fn on_expand(&mut self, space: &S, progress: &Progress<'_, S::State>) {
+        if let Some(goal) = progress.goal {
+            space.display_progress(progress.init, goal, &progress.open());
+        }
+    }
+}
+
+/// An observer doing nothing, the default one.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl<S: StateSpace + ?Sized> Observer<S> for NoObserver {
+    #[inline]
+    fn on_expand(&mut self, _space: &S, _progress: &Progress<'_, S::State>) {}
+}
+
+impl<S, F> Observer<S> for F
+where
+    S: StateSpace + ?Sized,
+    F: FnMut(&Progress<'_, S::State>),
+{
+    #[inline]
+    fn on_expand(&mut self, _space: &S, progress: &Progress<'_, S::State>) {
+        self(progress);
+    }
+}
+
+/// A builder configuring a search, created with [`StateSpace::search`].
+#[must_use]
+pub struct SearchBuilder<'a, S: ?Sized, A = BreadthFirstSearch, O = NoObserver, H = RandomState> {
+    space: &'a S,
+    algorithm: A,
+    observer: O,
+    hasher: H,
+    config: Config,
+}
+
+impl<'a, S: StateSpace + ?Sized> SearchBuilder<'a, S> {
+    /// Creates a breadth-first search of `space` without limits.
+    #[inline]
+    pub fn new(space: &'a S) -> Self {
+        Self {
+            space,
+            algorithm: BreadthFirstSearch,
+            observer: NoObserver,
+            hasher: RandomState::new(),
+            config: Config {
+                limits: Limits::default(),
+                tie_break: TieBreak::Fifo,
+                duplicate_detection: DuplicateDetection::ClosedSet,
+                cancellation: None,
+                check_costs: false,
+            },
+        }
+    }
+}
+
+impl<'a, S: StateSpace + ?Sized, A, O, H> SearchBuilder<'a, S, A, O, H> {
+    /// Selects the algorithm of the search.
+    #[inline]
+    pub fn algorithm<B: SearchAlgorithm<S>>(self, algorithm: B) -> SearchBuilder<'a, S, B, O, H> {
+        SearchBuilder {
+            space: self.space,
+            algorithm,
+            observer: self.observer,
+            hasher: self.hasher,
+            config: self.config,
+        }
+    }
+
+    /// Sets the observer of the search, [`NoObserver`] by default.
+    #[inline]
+    pub fn observer<P: Observer<S>>(self, observer: P) -> SearchBuilder<'a, S, A, P, H> {
+        SearchBuilder {
+            space: self.space,
+            algorithm: self.algorithm,
+            observer,
+            hasher: self.hasher,
+            config: self.config,
+        }
+    }
+
+    /// Sets the hasher of the table interning the states reached, see
+    /// [`StateArena`](crate::arena::StateArena).
+    #[inline]
+    pub fn hasher<G: BuildHasher>(self, hasher: G) -> SearchBuilder<'a, S, A, O, G> {
+        SearchBuilder {
+            space: self.space,
+            algorithm: self.algorithm,
+            observer: self.observer,
+            hasher,
+            config: self.config,
+        }
+    }
+
+    /// Sets the limits of the search.
+    #[inline]
+    pub const fn limits(mut self, limits: Limits) -> Self {
+        self.config.limits = limits;
+        self
+    }
+
+    /// Sets the order in which states with equal priorities are expanded.
+    #[inline]
+    pub const fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.config.tie_break = tie_break;
+        self
+    }
+
+    /// Sets how already reached states are detected,
+    /// [`DuplicateDetection::ClosedSet`] by default.
+    #[inline]
+    pub const fn duplicate_detection(mut self, duplicate_detection: DuplicateDetection) -> Self {
+        self.config.duplicate_detection = duplicate_detection;
+        self
+    }
+
+    /// Panics in debug builds when a transition has a negative cost, which the
+    /// algorithms expanding states by cost assume never happens.
+    ///
+    /// Negative costs are handled by
+    /// [`CostStateSpace::bellman_ford`](crate::CostStateSpace::bellman_ford).
+    #[inline]
+    pub const fn check_costs(mut self) -> Self {
+        self.config.check_costs = true;
+        self
+    }
+
+    /// Stops the search once `token` is cancelled.
+    #[inline]
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.config.cancellation = Some(token);
+        self
+    }
+}
+
+impl<S, A, O, H> SearchBuilder<'_, S, A, O, H>
+where
+    S: StateSpace + ?Sized,
+    A: SearchAlgorithm<S>,
+    O: Observer<S>,
+    H: BuildHasher,
+{
+    /// Runs the search from `init` to `goal`.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn run(mut self, init: S::State, goal: S::State) -> SearchResult<S::State> {
+        engine::search(
+            self.space,
+            &self.algorithm,
+            &mut self.observer,
+            &self.config,
+            self.hasher,
+            init,
+            slice::from_ref(&goal),
+        )
+    }
+
+    /// Expands every state reachable from `init`, within the limits, for
+    /// instance to count them with [`SearchResult::stats`] or to visit them
+    /// with an observer.
+    ///
+    /// The algorithms ordering states by priority expand them by cost as no
+    /// goal is given to the heuristic.
+    pub fn explore(mut self, init: S::State) -> SearchResult<S::State> {
+        engine::search(
+            self.space,
+            &self.algorithm,
+            &mut self.observer,
+            &self.config,
+            self.hasher,
+            init,
+            &[],
+        )
+    }
+
+    /// Runs the search from several sources to several goals, each source
+    /// being reached with the given cost, stopping at the first goal
+    /// reached.
+    ///
+    /// The search starts from the cheapest sources, which only matters for
+    /// the algorithms expanding states by cost or priority. The algorithms
+    /// using a heuristic are guided towards the closest goal according to it.
+    pub fn run_many(
+        mut self,
+        sources: impl IntoIterator<Item = (S::State, f32)>,
+        goals: impl IntoIterator<Item = S::State>,
+    ) -> MultiSearchResult<S::State> {
+        let sources: Vec<_> = sources.into_iter().collect();
+        let goals: Vec<_> = goals.into_iter().collect();
+
+        let state = engine::start(
+            self.space,
+            &self.algorithm,
+            &self.config,
+            self.hasher,
+            sources.iter().cloned(),
+            &goals,
+        );
+        let search = engine::resume(
+            self.space,
+            &self.algorithm,
+            &mut self.observer,
+            &self.config,
+            state,
+            &goals,
+        );
+
+        let (source, goal) = search.path.as_ref().map_or((None, None), |path| {
+            let first = &path[0];
+            let last = &path[path.len() - 1];
+            // The cheapest of duplicated sources is the one the search kept.
+            let source = sources
+                .iter()
+                .enumerate()
+                .filter(|(_, (source, _))| source == first)
+                .min_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b))
+                .map(|(index, _)| index);
+            (source, goals.iter().position(|goal| goal == last))
+        });
+
+        MultiSearchResult {
+            source,
+            goal,
+            search,
+        }
+    }
+
+    /// Expands every state reachable from `init`, within the limits, and
+    /// returns the costs of the paths reaching them along with their parents.
+    ///
+    /// The states are interned whatever the [`DuplicateDetection`] strategy.
+    /// The distances are the shortest ones if the algorithm expands states by
+    /// cost, like [`BreadthFirstSearch`] or
+    /// [`Dijkstra`](crate::algorithm::Dijkstra), and the search is not
+    /// interrupted.
+    pub fn distances(self, init: S::State) -> DistanceMap<S::State, H> {
+        self.distances_many([(init, 0.)])
+    }
+
+    /// Expands every state reachable from several sources, each reached with
+    /// the given cost, and returns the costs of the paths reaching them along
+    /// with their parents and sources, as [`SearchBuilder::distances`].
+    pub fn distances_many(
+        mut self,
+        sources: impl IntoIterator<Item = (S::State, f32)>,
+    ) -> DistanceMap<S::State, H> {
+        self.config.duplicate_detection = DuplicateDetection::ClosedSet;
+
+        let mut state = engine::start(
+            self.space,
+            &self.algorithm,
+            &self.config,
+            self.hasher,
+            sources,
+            &[],
+        );
+        let termination = engine::drive(
+            self.space,
+            &self.algorithm,
+            &mut self.observer,
+            &self.config,
+            &mut state,
+            &[],
+        )
+        .expect_err("Search without a goal should not find one");
+
+        DistanceMap::new(state, termination)
+    }
+
+    /// Resumes the search saved in `checkpoint`, towards its goals if it had
+    /// some.
+    ///
+    /// The limits apply to the whole search except for the time limit, which
+    /// counts from the resumption.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the algorithm does not expand states in the same order as
+    /// the one which took the checkpoint.
+    #[cfg(feature = "serde")]
+    pub fn resume(mut self, checkpoint: Checkpoint<S::State>) -> SearchResult<S::State> {
+        assert_eq!(
+            self.algorithm.frontier(),
+            checkpoint.frontier(),
+            "Search should be resumed with the algorithm which took the checkpoint"
+        );
+
+        let (state, goals) = checkpoint.restore(self.config.tie_break, self.hasher);
+        engine::resume(
+            self.space,
+            &self.algorithm,
+            &mut self.observer,
+            &self.config,
+            state,
+            &goals,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{AStar, DepthFirstSearch, Dijkstra};
+    use crate::test_utils::{allocations, Grid};
+    use crate::CostStateSpace;
+
+    #[test]
+    fn a_star_finds_shortest_path() {
+        let grid = Grid::open(5, 5);
+        let result = grid.search().algorithm(AStar).run((0, 0), (4, 3));
+
+        assert!(result.is_found());
+        assert_eq!(result.cost, Some(7.));
+        assert_eq!(result.path.unwrap().len(), 8);
+    }
+
+    #[test]
+    fn exhausted_when_unreachable() {
+        let grid = Grid::from_rows(&["...", "XXX", "..."]);
+        let result = grid.search().algorithm(Dijkstra).run((0, 0), (0, 2));
+
+        assert_eq!(result.termination, Termination::Exhausted);
+        assert_eq!(result.path, None);
+        assert_eq!(result.stats.expanded, 3);
+    }
+
+    #[test]
+    fn expansion_limit() {
+        let grid = Grid::open(10, 10);
+        let result = grid
+            .search()
+            .algorithm(DepthFirstSearch)
+            .limits(Limits {
+                max_expansions: Some(5),
+                ..Limits::default()
+            })
+            .run((0, 0), (9, 9));
+
+        assert_eq!(result.termination, Termination::ExpansionLimit);
+        assert_eq!(result.stats.expanded, 5);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let grid = Grid::open(10, 1);
+        let result = grid
+            .search()
+            .limits(Limits {
+                max_depth: Some(3),
+                ..Limits::default()
+            })
+            .run((0, 0), (9, 0));
+
+        assert_eq!(result.termination, Termination::DepthLimit);
+    }
+
+    #[test]
+    fn observer_sees_every_expansion() {
+        let grid = Grid::open(4, 4);
+        let mut expanded = Vec::new();
+        let result = grid
+            .search()
+            .algorithm(AStar)
+            .observer(|progress: &Progress<'_, (usize, usize)>| expanded.push(*progress.current))
+            .run((0, 0), (3, 3));
+
+        assert_eq!(expanded.len(), result.stats.expanded);
+        assert_eq!(expanded[0], (0, 0));
+    }
+
+    #[test]
+    fn display_progress_is_opt_in() {
+        use std::cell::Cell;
+
+        struct Counted(Cell<usize>);
+
+        impl StateSpace for Counted {
+            type State = u8;
+
+            fn neighbours(&self, state: &u8) -> Vec<u8> {
+                vec![state.saturating_add(1)]
+            }
+
+            fn display_progress(&self, _init: &u8, goal: &u8, open: &[u8]) {
+                assert_eq!((*goal, open.len()), (5, 1));
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        impl CostStateSpace for Counted {
+            fn cost(&self, _current: &u8, _next: &u8) -> f32 {
+                1.
+            }
+        }
+
+        let space = Counted(Cell::new(0));
+        space.breadth_first_search(0, 5);
+        space.distances_from(0, None);
+        space.nearest(vec![0], vec![5]);
+        space.voronoi(vec![0, 3], None);
+        assert_eq!(space.0.get(), 0);
+
+        // Without a goal there is nothing to display.
+        space.search().observer(DisplayProgress).explore(0);
+        assert_eq!(space.0.get(), 0);
+
+        space.search().observer(DisplayProgress).run(0, 5);
+        assert_eq!(space.0.get(), 5);
+    }
+
+    #[test]
+    fn tie_break_highest_cost_expands_less() {
+        let grid = Grid::open(8, 8);
+        let fifo = grid.search().algorithm(AStar).run((0, 0), (7, 7));
+        let deepest = grid
+            .search()
+            .algorithm(AStar)
+            .tie_break(TieBreak::HighestCost)
+            .run((0, 0), (7, 7));
+
+        assert_eq!(fifo.cost, deepest.cost);
+        assert!(deepest.stats.expanded < fifo.stats.expanded);
+    }
+
+    #[test]
+    fn every_duplicate_detection_finds_shortest_path() {
+        let grid = Grid::from_rows(&["....", ".XX.", "...X", "X..."]);
+
+        for duplicate_detection in [
+            DuplicateDetection::None,
+            DuplicateDetection::Parent,
+            DuplicateDetection::Path,
+            DuplicateDetection::ClosedSet,
+            DuplicateDetection::Transposition(4),
+        ] {
+            let result = grid
+                .search()
+                .algorithm(AStar)
+                .duplicate_detection(duplicate_detection)
+                .run((0, 0), (3, 3));
+
+            assert_eq!(result.cost, Some(6.), "{duplicate_detection:?}");
+        }
+    }
+
+    #[test]
+    fn path_detection_exhausts_finite_space() {
+        let grid = Grid::from_rows(&["...", "X.X"]);
+        let result = grid
+            .search()
+            .algorithm(DepthFirstSearch)
+            .duplicate_detection(DuplicateDetection::Path)
+            .run((0, 0), (0, 1));
+
+        assert_eq!(result.termination, Termination::Exhausted);
+    }
+
+    #[test]
+    fn custom_hasher() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        let grid = Grid::open(5, 5);
+        let result = grid
+            .search()
+            .algorithm(AStar)
+            .hasher(BuildHasherDefault::<DefaultHasher>::default())
+            .run((0, 0), (4, 4));
+
+        assert_eq!(result.cost, Some(8.));
+    }
+
+    #[test]
+    fn explore_counts_reachable_states() {
+        let grid = Grid::from_rows(&["..X.", "..X.", "XXX."]);
+        let result = grid.search().observer(NoObserver).explore((0, 0));
+
+        assert_eq!(result.termination, Termination::Exhausted);
+        assert_eq!(result.stats.expanded, 4);
+        assert_eq!(result.stats.omission_probability, None);
+    }
+
+    #[test]
+    fn probabilistic_detection_covers_small_space() {
+        let grid = Grid::open(20, 20);
+
+        for duplicate_detection in [
+            DuplicateDetection::Bitstate { log2_bits: 20 },
+            DuplicateDetection::Bloom {
+                bits: 1 << 16,
+                hashes: 3,
+            },
+            DuplicateDetection::HashCompaction,
+        ] {
+            let result = grid
+                .search()
+                .algorithm(DepthFirstSearch)
+                .duplicate_detection(duplicate_detection)
+                .explore((0, 0));
+            let omission = result.stats.omission_probability.unwrap();
+
+            assert_eq!(result.stats.expanded, 400, "{duplicate_detection:?}");
+            assert!(omission > 0. && omission < 0.1, "{:?}", duplicate_detection);
+        }
+    }
+
+    #[test]
+    fn expansions_do_not_allocate() {
+        struct Binary;
+
+        impl StateSpace for Binary {
+            type State = u32;
+
+            fn neighbours(&self, state: &u32) -> Vec<u32> {
+                vec![2 * state, 2 * state + 1]
+            }
+
+            fn for_each_neighbour(&self, state: &u32, f: &mut dyn FnMut(u32)) {
+                f(2 * state);
+                f(2 * state + 1);
+            }
+        }
+
+        let mut warm = 0;
+        let mut last = 0;
+        let result = Binary
+            .search()
+            .algorithm(DepthFirstSearch)
+            .duplicate_detection(DuplicateDetection::None)
+            .limits(Limits {
+                max_depth: Some(12),
+                ..Limits::default()
+            })
+            .observer(|progress: &Progress<'_, u32>| {
+                // Once the subtree of the first successor is searched.
+                if progress.stats.expanded == 1 << 11 {
+                    warm = allocations();
+                }
+                last = allocations();
+            })
+            .explore(1);
+
+        assert_eq!(result.stats.expanded, (1 << 12) - 1);
+        assert_eq!(last, warm);
+    }
+
+    #[test]
+    fn probabilistic_detection_frees_finished_subtrees() {
+        let mut max_nodes = 0;
+        let mut max_states = 0;
+        let result = Grid::open(50, 50)
+            .search()
+            .duplicate_detection(DuplicateDetection::HashCompaction)
+            .observer(|progress: &Progress<'_, (usize, usize)>| {
+                max_nodes = max_nodes.max(progress.nodes.len());
+                max_states = max_states.max(progress.states.len());
+            })
+            .explore((0, 0));
+
+        assert_eq!(result.stats.expanded, 2500);
+        // Only the paths to the open states are kept.
+        assert!(max_nodes < 1500, "{}", max_nodes);
+        assert_eq!(max_states, max_nodes);
+    }
+
+    #[test]
+    fn tree_search_frees_finished_subtrees() {
+        let mut max_nodes = 0;
+        let result = Grid::open(5, 5)
+            .search()
+            .algorithm(DepthFirstSearch)
+            .duplicate_detection(DuplicateDetection::Path)
+            .limits(Limits {
+                max_depth: Some(12),
+                ..Limits::default()
+            })
+            .observer(|progress: &Progress<'_, (usize, usize)>| {
+                max_nodes = max_nodes.max(progress.nodes.len());
+            })
+            .explore((0, 0));
+
+        assert_eq!(result.stats.expanded, 6441);
+        // The open nodes and their ancestors, at most 4 per level.
+        assert!(max_nodes <= 4 * 12 + 1, "{}", max_nodes);
+    }
+
+    #[test]
+    fn closed_set_expands_less_than_tree_search() {
+        let grid = Grid::open(4, 4);
+        let run = |duplicate_detection| {
+            grid.search()
+                .duplicate_detection(duplicate_detection)
+                .run((0, 0), (3, 3))
+        };
+
+        let tree = run(DuplicateDetection::None);
+        let graph = run(DuplicateDetection::ClosedSet);
+
+        assert_eq!(tree.cost, graph.cost);
+        assert!(graph.stats.expanded < tree.stats.expanded);
+    }
+
+    #[test]
+    fn cancelled_search_stops() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let result = Grid::open(4, 4)
+            .search()
+            .cancellation(token)
+            .run((0, 0), (3, 3));
+
+        assert_eq!(result.termination, Termination::Cancelled);
+        assert_eq!(result.stats.expanded, 0);
+    }
+
+    #[test]
+    fn nearest_source_and_goal() {
+        let grid = Grid::open(10, 10);
+        let sources = [((0, 0), 0.), ((9, 9), 0.), ((5, 0), 3.)];
+        let goals = [(0, 9), (7, 0), (9, 5)];
+
+        let dijkstra = grid.search().algorithm(Dijkstra).run_many(sources, goals);
+        let a_star = grid.search().algorithm(AStar).run_many(sources, goals);
+
+        for result in [dijkstra, a_star] {
+            assert_eq!((result.source, result.goal), (Some(1), Some(2)));
+            assert_eq!(result.search.cost, Some(4.));
+        }
+    }
+
+    #[test]
+    fn source_offsets() {
+        let grid = Grid::open(10, 1);
+        let run = |offset| {
+            grid.search()
+                .algorithm(Dijkstra)
+                .run_many([((0, 0), 0.), ((5, 0), offset)], [(7, 0)])
+        };
+
+        let result = run(3.);
+        assert_eq!(result.source, Some(1));
+        assert_eq!(result.search.cost, Some(5.));
+
+        let result = run(6.);
+        assert_eq!(result.source, Some(0));
+        assert_eq!(result.search.cost, Some(7.));
+    }
+
+    #[test]
+    fn unreachable_goals() {
+        let grid = Grid::from_rows(&["..X.", "..X."]);
+        let result = grid.nearest(vec![(0, 0), (1, 1)], vec![(3, 0), (3, 1)]);
+
+        assert_eq!((result.source, result.goal), (None, None));
+        assert_eq!(result.search.termination, Termination::Exhausted);
+        assert_eq!(result.search.stats.expanded, 4);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Transition costs should be non-negative")]
+    fn negative_costs_are_checked() {
+        struct Rewarding;
+
+        impl StateSpace for Rewarding {
+            type State = u8;
+
+            fn neighbours(&self, state: &u8) -> Vec<u8> {
+                vec![state.saturating_add(1)]
+            }
+        }
+
+        impl CostStateSpace for Rewarding {
+            fn cost(&self, _current: &u8, _next: &u8) -> f32 {
+                -1.
+            }
+        }
+
+        Rewarding
+            .search()
+            .algorithm(Dijkstra)
+            .check_costs()
+            .run(0, 3);
+    }
+}
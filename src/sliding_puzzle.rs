@@ -0,0 +1,650 @@
+//! The sliding-tile puzzle, a classic benchmark of heuristic searches.
+//!
+//! A board of `width × height` cells holds the tiles `1` to
+//! `width * height - 1` and a blank, numbered `0`. Each move slides a tile
+//! adjacent to the blank into it, and the goal is to order the tiles, the
+//! blank being last.
+//!
+//! Only half of the boards can reach a given goal, which
+//! [`SlidingPuzzle::is_solvable`] tells without searching. The heuristics
+//! are, from the weakest to the strongest, the Manhattan distance of the
+//! tiles, the linear conflicts added to it, and disjoint additive
+//! [`PatternDatabases`].
+//!
+//! ```
+//! # use state_space::sliding_puzzle::{Board, SlidingPuzzle};
+//! # use state_space::HeuristicStateSpace;
+//! let puzzle = SlidingPuzzle::new(3, 3);
+//! let board = Board::new(vec![
+//!     1, 2, 3,
+//!     4, 0, 6,
+//!     7, 5, 8,
+//! ]);
+//!
+//! assert!(puzzle.is_solvable(&board, &puzzle.goal()));
+//! assert_eq!(puzzle.a_star(board.clone(), puzzle.goal()).len(), 3);
+//! assert_eq!(puzzle.ida_star(board, puzzle.goal()).len(), 3);
+//! ```
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::codec::{partial_permutations, rank_partial};
+use crate::pattern_database::{Abstraction, PatternDatabase};
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// The tiles of a board, row by row, `0` being the blank.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Board {
+    tiles: Vec<u8>,
+}
+
+impl Board {
+    /// Creates a board from its tiles, row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tiles are not the numbers from `0` to their count
+    /// minus one.
+    #[must_use]
+    pub fn new(tiles: Vec<u8>) -> Self {
+        let mut seen = vec![false; tiles.len()];
+        for &tile in &tiles {
+            assert!(
+                usize::from(tile) < tiles.len() && !seen[usize::from(tile)],
+                "Tiles should be the numbers from 0 to {}",
+                tiles.len() - 1
+            );
+            seen[usize::from(tile)] = true;
+        }
+
+        Self { tiles }
+    }
+
+    /// Returns the tiles, row by row.
+    #[inline]
+    #[must_use]
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    /// Returns the index of the blank.
+    #[inline]
+    #[must_use]
+    pub fn blank(&self) -> usize {
+        self.position(0)
+    }
+
+    /// Returns the index of `tile`.
+    fn position(&self, tile: u8) -> usize {
+        self.tiles
+            .iter()
+            .position(|&t| t == tile)
+            .expect("Board should contain every tile")
+    }
+
+    /// Returns the index of each tile.
+    fn positions(&self) -> Vec<usize> {
+        let mut positions = vec![0; self.tiles.len()];
+        for (position, &tile) in self.tiles.iter().enumerate() {
+            positions[usize::from(tile)] = position;
+        }
+        positions
+    }
+}
+
+/// A heuristic of the [`SlidingPuzzle`].
+#[derive(Debug, Clone)]
+pub enum PuzzleHeuristic {
+    /// The sum of the Manhattan distances of the tiles to their goal cells.
+    Manhattan,
+    /// The Manhattan distance, plus two moves for each tile which has to
+    /// leave its goal row or column to let other tiles of that line past.
+    LinearConflict,
+    /// The sum of the distances in disjoint pattern databases, falling back
+    /// to [`PuzzleHeuristic::LinearConflict`] for the goals they have not
+    /// been built for.
+    PatternDatabases(Arc<PatternDatabases>),
+}
+
+/// The sliding-tile puzzle of `width × height` cells, where moves cost 1.
+#[derive(Debug, Clone)]
+pub struct SlidingPuzzle {
+    width: usize,
+    height: usize,
+    heuristic: PuzzleHeuristic,
+    goal: Board,
+    /// The index of each tile in the goal.
+    goal_positions: Vec<usize>,
+}
+
+impl SlidingPuzzle {
+    /// Creates the puzzle of `width × height` cells, with the linear
+    /// conflict heuristic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is less than 2, or if there are more
+    /// than 256 cells.
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(
+            width >= 2 && height >= 2 && width * height <= 256,
+            "Puzzle should be at least 2 × 2 and have at most 256 cells"
+        );
+
+        let goal = Board {
+            tiles: (1..=width * height)
+                .map(|tile| u8::try_from(tile % (width * height)).unwrap_or(0))
+                .collect(),
+        };
+
+        Self {
+            width,
+            height,
+            heuristic: PuzzleHeuristic::LinearConflict,
+            goal_positions: goal.positions(),
+            goal,
+        }
+    }
+
+    /// Sets the heuristic.
+    #[inline]
+    #[must_use]
+    pub fn with_heuristic(mut self, heuristic: PuzzleHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the ordered board, the blank being last.
+    #[inline]
+    #[must_use]
+    pub fn goal(&self) -> Board {
+        self.goal.clone()
+    }
+
+    /// Returns whether `board` can reach `goal`.
+    ///
+    /// Each move swaps the blank with a tile and moves it by one cell, so
+    /// the parity of the permutation from `board` to `goal` has to be the
+    /// parity of the distance between their blanks, which also suffices.
+    #[must_use]
+    pub fn is_solvable(&self, board: &Board, goal: &Board) -> bool {
+        let goal_positions = goal.positions();
+
+        // The parity of a permutation is the parity of its length minus its
+        // number of cycles.
+        let mut visited = vec![false; board.tiles.len()];
+        let mut cycles = 0;
+        for start in 0..board.tiles.len() {
+            if visited[start] {
+                continue;
+            }
+            cycles += 1;
+
+            let mut position = start;
+            while !visited[position] {
+                visited[position] = true;
+                position = goal_positions[usize::from(board.tiles[position])];
+            }
+        }
+
+        let blank_distance = self.distance(board.blank(), goal.blank());
+        (board.tiles.len() - cycles) % 2 == blank_distance % 2
+    }
+
+    /// Returns a board drawn uniformly among those which can reach the goal.
+    pub fn random_board<R: Rng + ?Sized>(&self, rng: &mut R) -> Board {
+        let mut board = self.goal();
+        board.tiles.shuffle(rng);
+
+        if !self.is_solvable(&board, &self.goal) {
+            // Swapping two tiles changes the parity of the permutation.
+            let mut tiles = (0..board.tiles.len()).filter(|&i| board.tiles[i] != 0);
+            let (first, second) = (tiles.next(), tiles.next());
+            if let (Some(first), Some(second)) = (first, second) {
+                board.tiles.swap(first, second);
+            }
+        }
+
+        board
+    }
+
+    /// Returns the sum of the Manhattan distances of the tiles of `board` to
+    /// their cells in `goal`.
+    #[must_use]
+    pub fn manhattan(&self, board: &Board, goal: &Board) -> usize {
+        let goal_positions = self.goal_positions(goal);
+
+        board
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(position, &tile)| self.distance(position, goal_positions[usize::from(tile)]))
+            .sum()
+    }
+
+    /// Returns the Manhattan distance plus two moves for each tile which has
+    /// to leave its goal row or column to let the others of the line past.
+    ///
+    /// In each line, the tiles in their goal line have to end in the order of
+    /// their goal cells, so those outside of a longest increasing subsequence
+    /// leave the line and come back.
+    #[must_use]
+    pub fn linear_conflict(&self, board: &Board, goal: &Board) -> usize {
+        let goal_positions = self.goal_positions(goal);
+        let mut conflicts = 0;
+
+        let mut line = Vec::with_capacity(self.width.max(self.height));
+        for y in 0..self.height {
+            line.clear();
+            line.extend((0..self.width).filter_map(|x| {
+                let tile = board.tiles[x + y * self.width];
+                let goal = goal_positions[usize::from(tile)];
+                (tile != 0 && goal / self.width == y).then_some(goal % self.width)
+            }));
+            conflicts += line.len() - longest_increasing(&line);
+        }
+        for x in 0..self.width {
+            line.clear();
+            line.extend((0..self.height).filter_map(|y| {
+                let tile = board.tiles[x + y * self.width];
+                let goal = goal_positions[usize::from(tile)];
+                (tile != 0 && goal % self.width == x).then_some(goal / self.width)
+            }));
+            conflicts += line.len() - longest_increasing(&line);
+        }
+
+        self.manhattan(board, goal) + 2 * conflicts
+    }
+
+    fn goal_positions<'a>(&'a self, goal: &Board) -> Cow<'a, [usize]> {
+        if goal == &self.goal {
+            Cow::Borrowed(&self.goal_positions)
+        } else {
+            Cow::Owned(goal.positions())
+        }
+    }
+
+    /// Returns the Manhattan distance between two cells.
+    const fn distance(&self, a: usize, b: usize) -> usize {
+        (a % self.width).abs_diff(b % self.width) + (a / self.width).abs_diff(b / self.width)
+    }
+}
+
+/// Returns the cells adjacent to `position` on a board of `width × height`
+/// cells.
+fn adjacent(width: usize, height: usize, position: usize) -> impl Iterator<Item = usize> {
+    let (x, y) = (position % width, position / width);
+
+    IntoIterator::into_iter([
+        (y > 0).then(|| position - width),
+        (x + 1 < width).then(|| position + 1),
+        (y + 1 < height).then(|| position + width),
+        (x > 0).then(|| position - 1),
+    ])
+    .flatten()
+}
+
+/// Returns the length of the longest increasing subsequence of `values`.
+fn longest_increasing(values: &[usize]) -> usize {
+    let mut lengths = vec![1; values.len()];
+    for i in 0..values.len() {
+        for j in 0..i {
+            if values[j] < values[i] {
+                lengths[i] = lengths[i].max(lengths[j] + 1);
+            }
+        }
+    }
+    lengths.into_iter().max().unwrap_or(0)
+}
+
+impl StateSpace for SlidingPuzzle {
+    type State = Board;
+
+    fn for_each_neighbour(&self, state: &Board, f: &mut dyn FnMut(Board)) {
+        let blank = state.blank();
+        for position in adjacent(self.width, self.height, blank) {
+            let mut next = state.clone();
+            next.tiles.swap(blank, position);
+            f(next);
+        }
+    }
+}
+
+impl CostStateSpace for SlidingPuzzle {
+    #[inline]
+    fn cost(&self, _current: &Board, _next: &Board) -> f32 {
+        1.
+    }
+}
+
+impl HeuristicStateSpace for SlidingPuzzle {
+    #[allow(clippy::cast_precision_loss)]
+    fn heuristic(&self, state: &Board, goal: &Board) -> f32 {
+        let moves = match &self.heuristic {
+            PuzzleHeuristic::Manhattan => self.manhattan(state, goal),
+            PuzzleHeuristic::PatternDatabases(databases) if &databases.goal == goal => {
+                databases.lookup(state)
+            }
+            PuzzleHeuristic::LinearConflict | PuzzleHeuristic::PatternDatabases(_) => {
+                self.linear_conflict(state, goal)
+            }
+        };
+
+        moves as f32
+    }
+}
+
+/// Disjoint additive pattern databases of the [`SlidingPuzzle`].
+///
+/// The tiles are split into disjoint patterns, and the database of each
+/// pattern, abstracting the puzzle by a [`TilePattern`], stores the number of
+/// moves of its tiles needed to bring them to their goal cells, whatever the
+/// other tiles. Since each move moves a single tile, the sum over the
+/// patterns is a lower bound of the number of moves.
+#[derive(Debug, Clone)]
+pub struct PatternDatabases {
+    goal: Board,
+    databases: Vec<PatternDatabase<TilePattern>>,
+}
+
+impl PatternDatabases {
+    /// Builds the databases of `patterns` for the goal of `puzzle`, with
+    /// backward searches from the goal where only the moves of the tiles of
+    /// the pattern count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the patterns are not disjoint sets of tiles, the blank
+    /// excluded, or if the database of a pattern does not fit in memory.
+    #[must_use]
+    pub fn new(puzzle: &SlidingPuzzle, patterns: &[&[u8]]) -> Self {
+        let cells = puzzle.width * puzzle.height;
+        let mut used = vec![false; cells];
+        for &tile in patterns.iter().copied().flatten() {
+            let tile = usize::from(tile);
+            assert!(
+                tile != 0 && tile < cells && !used[tile],
+                "Patterns should be disjoint sets of tiles"
+            );
+            used[tile] = true;
+        }
+
+        let goal = puzzle.goal();
+        Self {
+            databases: patterns
+                .iter()
+                .map(|tiles| {
+                    let pattern = TilePattern::new(puzzle.width, puzzle.height, tiles.to_vec());
+                    PatternDatabase::dijkstra(pattern, &goal)
+                })
+                .collect(),
+            goal,
+        }
+    }
+
+    /// Returns the database of each pattern.
+    #[inline]
+    #[must_use]
+    pub fn databases(&self) -> &[PatternDatabase<TilePattern>] {
+        &self.databases
+    }
+
+    /// Returns the sum over the patterns of the moves of their tiles needed
+    /// to reach the goal from `board`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `board` does not have as many cells as the puzzle the
+    /// databases have been built for.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn lookup(&self, board: &Board) -> usize {
+        self.databases
+            .iter()
+            .map(|database| database.lookup(board) as usize)
+            .sum()
+    }
+}
+
+/// The abstraction of the [`SlidingPuzzle`] keeping the cells of some tiles
+/// and of the blank, the moves of the other tiles costing nothing.
+///
+/// The abstract states are the cells of the tiles followed by the cell of
+/// the blank, indexed by their rank among the sequences of distinct cells,
+/// so a pattern of `k` tiles has `n!/(n-k-1)!` abstract states for `n`
+/// cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TilePattern {
+    width: usize,
+    height: usize,
+    tiles: Vec<u8>,
+}
+
+impl TilePattern {
+    /// Creates the abstraction of the puzzle of `width × height` cells
+    /// keeping `tiles`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tiles are not distinct tiles of the puzzle, the blank
+    /// excluded, or if the abstract states cannot be counted in 64 bits.
+    #[must_use]
+    pub fn new(width: usize, height: usize, tiles: Vec<u8>) -> Self {
+        let cells = width * height;
+        assert!(
+            tiles.iter().enumerate().all(|(i, &tile)| tile != 0
+                && usize::from(tile) < cells
+                && !tiles[..i].contains(&tile)),
+            "Pattern should be distinct tiles of the puzzle"
+        );
+        assert!(
+            partial_permutations(cells, tiles.len() + 1).is_some(),
+            "Pattern should be small enough to be indexed"
+        );
+
+        Self {
+            width,
+            height,
+            tiles,
+        }
+    }
+
+    /// Returns the tiles of the pattern.
+    #[inline]
+    #[must_use]
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+}
+
+impl StateSpace for TilePattern {
+    type State = Vec<u8>;
+
+    fn for_each_neighbour(&self, state: &Vec<u8>, f: &mut dyn FnMut(Vec<u8>)) {
+        let blank = state[self.tiles.len()];
+        for cell in adjacent(self.width, self.height, usize::from(blank)).map(byte) {
+            let mut next = state.clone();
+            next[self.tiles.len()] = cell;
+            if let Some(tile) = next[..self.tiles.len()].iter().position(|&c| c == cell) {
+                next[tile] = blank;
+            }
+            f(next);
+        }
+    }
+}
+
+impl CostStateSpace for TilePattern {
+    /// Returns 1 if a tile of the pattern moves, 0 otherwise.
+    #[inline]
+    fn cost(&self, current: &Vec<u8>, next: &Vec<u8>) -> f32 {
+        let tiles = self.tiles.len();
+        if current[..tiles] == next[..tiles] {
+            0.
+        } else {
+            1.
+        }
+    }
+}
+
+impl Abstraction for TilePattern {
+    type Concrete = Board;
+
+    /// Returns the cells of the tiles of the pattern followed by the cell of
+    /// the blank.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `board` does not have as many cells as the puzzle.
+    fn abstract_state(&self, board: &Board) -> Vec<u8> {
+        assert_eq!(
+            board.tiles.len(),
+            self.width * self.height,
+            "Board should have as many cells as the puzzle of the pattern"
+        );
+
+        let positions = board.positions();
+        self.tiles
+            .iter()
+            .chain(Some(&0))
+            .map(|&tile| byte(positions[usize::from(tile)]))
+            .collect()
+    }
+
+    #[inline]
+    fn size(&self) -> usize {
+        partial_permutations(self.width * self.height, self.tiles.len() + 1)
+            .and_then(|size| usize::try_from(size).ok())
+            .expect("Pattern database should fit in memory")
+    }
+
+    #[inline]
+    fn index(&self, state: &Vec<u8>) -> usize {
+        usize::try_from(rank_partial(state, self.width * self.height))
+            .expect("Pattern database should fit in memory")
+    }
+}
+
+/// Returns a cell of a puzzle, which has at most 256 of them, as a byte.
+fn byte(cell: usize) -> u8 {
+    u8::try_from(cell).expect("Puzzle should have at most 256 cells")
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::ida_star::ida_star;
+
+    #[test]
+    fn solvability_matches_reachability() {
+        let puzzle = SlidingPuzzle::new(3, 2);
+        let reachable = puzzle.distances_from(puzzle.goal(), None);
+        assert_eq!(reachable.len(), 360);
+
+        // Every permutation of the 6 tiles, by Heap's algorithm.
+        let mut tiles: Vec<u8> = (0..6).collect();
+        let mut counters = [0; 6];
+        let mut checked = 1;
+        assert!(puzzle.is_solvable(&Board::new(tiles.clone()), &puzzle.goal()));
+        let mut i = 0;
+        while i < 6 {
+            if counters[i] < i {
+                tiles.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+                let board = Board::new(tiles.clone());
+                assert_eq!(
+                    puzzle.is_solvable(&board, &puzzle.goal()),
+                    reachable.contains(&board)
+                );
+                checked += 1;
+                counters[i] += 1;
+                i = 0;
+            } else {
+                counters[i] = 0;
+                i += 1;
+            }
+        }
+        assert_eq!(checked, 720);
+    }
+
+    #[test]
+    fn heuristics_are_admissible_and_ordered() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let databases = Arc::new(PatternDatabases::new(
+            &puzzle,
+            &[&[1, 2, 3, 4], &[5, 6, 7, 8]],
+        ));
+        let goal = puzzle.goal();
+
+        for _ in 0..10 {
+            let board = puzzle.random_board(&mut rng);
+            assert!(puzzle.is_solvable(&board, &goal));
+
+            let moves = puzzle.a_star(board.clone(), goal.clone()).len() - 1;
+
+            let manhattan = puzzle.manhattan(&board, &goal);
+            let conflicts = puzzle.linear_conflict(&board, &goal);
+            let pdb = databases.lookup(&board);
+            assert!(manhattan <= conflicts && conflicts <= moves);
+            assert!(manhattan <= pdb && pdb <= moves);
+        }
+        assert_eq!(databases.lookup(&goal), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "as many cells")]
+    fn databases_check_board_size() {
+        let puzzle = SlidingPuzzle::new(3, 2);
+        let databases = PatternDatabases::new(&puzzle, &[&[1, 2, 3]]);
+        assert_eq!(databases.databases()[0].bytes(), 6 * 5 * 4 * 3);
+
+        let _ = databases.lookup(&SlidingPuzzle::new(3, 3).goal());
+    }
+
+    #[test]
+    fn stronger_heuristics_expand_less() {
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let board = puzzle.random_board(&mut StdRng::seed_from_u64(3));
+        let databases = PatternDatabases::new(&puzzle, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+
+        let expanded: Vec<_> = [
+            PuzzleHeuristic::Manhattan,
+            PuzzleHeuristic::LinearConflict,
+            PuzzleHeuristic::PatternDatabases(Arc::new(databases)),
+        ]
+        .iter()
+        .map(|heuristic| {
+            let puzzle = puzzle.clone().with_heuristic(heuristic.clone());
+            let a_star = puzzle.a_star(board.clone(), puzzle.goal());
+            let ida = ida_star(&puzzle, board.clone(), &puzzle.goal());
+
+            assert_eq!(ida.path.map(|path| path.len()), Some(a_star.len()));
+            ida.stats.expanded
+        })
+        .collect();
+
+        assert!(expanded[0] > expanded[1] && expanded[1] > expanded[2]);
+    }
+}
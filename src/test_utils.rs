@@ -0,0 +1,97 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// The system allocator counting the allocations of each thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations made by the current thread so far.
+pub fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// A 4-connected grid with unit costs, `X` being a wall.
+pub struct Grid {
+    width: usize,
+    height: usize,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    pub fn open(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            walls: vec![false; width * height],
+        }
+    }
+
+    pub fn from_rows(rows: &[&str]) -> Self {
+        Self {
+            width: rows[0].len(),
+            height: rows.len(),
+            walls: rows
+                .iter()
+                .flat_map(|r| r.chars().map(|c| c == 'X'))
+                .collect(),
+        }
+    }
+
+    fn is_free(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height && !self.walls[x + y * self.width]
+    }
+}
+
+impl StateSpace for Grid {
+    type State = (usize, usize);
+
+    fn neighbours(&self, &(x, y): &Self::State) -> Vec<Self::State> {
+        let mut neighbours = vec![(x + 1, y), (x, y + 1)];
+        if x > 0 {
+            neighbours.push((x - 1, y));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1));
+        }
+
+        neighbours.retain(|&s| self.is_free(s));
+        neighbours
+    }
+}
+
+impl CostStateSpace for Grid {
+    fn cost(&self, _current: &Self::State, _next: &Self::State) -> f32 {
+        1.
+    }
+}
+
+impl HeuristicStateSpace for Grid {
+    #[allow(clippy::cast_precision_loss)]
+    fn heuristic(&self, state: &Self::State, goal: &Self::State) -> f32 {
+        (state.0.abs_diff(goal.0) + state.1.abs_diff(goal.1)) as f32
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::arena::{StateArena, StateId};
+use crate::engine::Node;
+use crate::search::DuplicateDetection;
+
+/// A state admitted by [`Visited`], which tells whether it is stored yet.
+pub enum Admitted<T> {
+    /// The identifier of the state, already stored in the arena.
+    Stored(StateId),
+    /// The state itself, to be stored by its node.
+    Owned(T),
+}
+
+/// The bookkeeping of the states already reached, following a
+/// [`DuplicateDetection`] strategy.
+pub enum Visited<T> {
+    None,
+    Parent,
+    Path,
+    // The best costs indexed by the identifiers of the interned states.
+    ClosedSet(Vec<f32>),
+    Transposition(Lru<T>),
+    Bloom(BloomFilter),
+    HashCompaction(HashSet<u64>, Omissions),
+}
+
+impl<T: Clone + Eq + Hash> Visited<T> {
+    pub fn new(duplicate_detection: DuplicateDetection) -> Self {
+        match duplicate_detection {
+            DuplicateDetection::None => Self::None,
+            DuplicateDetection::Parent => Self::Parent,
+            DuplicateDetection::Path => Self::Path,
+            DuplicateDetection::ClosedSet => Self::ClosedSet(Vec::new()),
+            DuplicateDetection::Transposition(capacity) => Self::Transposition(Lru::new(capacity)),
+            DuplicateDetection::Bitstate { log2_bits } => {
+                let bits = 1_usize
+                    .checked_shl(log2_bits)
+                    .expect("Bitstate table should be addressable");
+                Self::Bloom(BloomFilter::new(bits, 1))
+            }
+            DuplicateDetection::Bloom { bits, hashes } => {
+                Self::Bloom(BloomFilter::new(bits, hashes))
+            }
+            DuplicateDetection::HashCompaction => {
+                Self::HashCompaction(HashSet::new(), Omissions::default())
+            }
+        }
+    }
+
+    /// Records an initial state reached with `cost`, returning it unless it
+    /// has already been recorded.
+    ///
+    /// The initial states should be recorded by nondecreasing cost.
+    pub fn insert_root<H: BuildHasher>(
+        &mut self,
+        arena: &mut StateArena<T, H>,
+        state: T,
+        cost: f32,
+    ) -> Option<Admitted<T>> {
+        let inserted = match self {
+            Self::None | Self::Parent | Self::Path => true,
+            Self::ClosedSet(best_cost) => {
+                let (id, inserted) = arena.intern(state);
+                if !inserted {
+                    return None;
+                }
+                best_cost.push(cost);
+                return Some(Admitted::Stored(id));
+            }
+            Self::Transposition(lru) => {
+                let inserted = lru.peek(&state).is_none();
+                if inserted {
+                    lru.insert(state.clone(), cost);
+                }
+                inserted
+            }
+            Self::Bloom(bloom) => bloom.insert(hash(&state)),
+            Self::HashCompaction(hashes, _) => hashes.insert(hash(&state)),
+        };
+
+        inserted.then(|| self.own(arena, state))
+    }
+
+    /// Returns whether the states are owned by the nodes of the search tree
+    /// rather than interned, so that the nodes can be released once they
+    /// lead to no open node.
+    pub const fn reclaims(&self) -> bool {
+        !matches!(self, Self::ClosedSet(_))
+    }
+
+    /// Hands `state` to its node if it can be released, or stores it.
+    fn own<H>(&self, arena: &mut StateArena<T, H>, state: T) -> Admitted<T> {
+        if self.reclaims() {
+            Admitted::Owned(state)
+        } else {
+            Admitted::Stored(arena.push(state))
+        }
+    }
+
+    /// Returns whether a cheaper path to the state `id` has been found since
+    /// it was reached with `cost`.
+    pub fn is_stale<H>(&self, arena: &StateArena<T, H>, id: StateId, cost: f32) -> bool {
+        match self {
+            Self::None | Self::Parent | Self::Path | Self::Bloom(_) | Self::HashCompaction(..) => {
+                false
+            }
+            Self::ClosedSet(best_cost) => cost > best_cost[id.index()],
+            Self::Transposition(lru) => lru.peek(&arena[id]).is_some_and(|c| cost > c),
+        }
+    }
+
+    /// Returns `state`, a successor of the node `parent` reached with `cost`,
+    /// if it should be opened, recording it if so.
+    pub fn admit<H: BuildHasher>(
+        &mut self,
+        arena: &mut StateArena<T, H>,
+        nodes: &[Node],
+        parent: usize,
+        state: T,
+        cost: f32,
+        reopens: bool,
+    ) -> Option<Admitted<T>> {
+        let admitted = match self {
+            Self::None => true,
+            Self::Parent => {
+                let node = &nodes[parent];
+                arena[node.state] != state
+                    && node
+                        .parent
+                        .is_none_or(|grandparent| arena[nodes[grandparent].state] != state)
+            }
+            Self::Path => {
+                let mut current = Some(parent);
+                while let Some(index) = current {
+                    if arena[nodes[index].state] == state {
+                        return None;
+                    }
+                    current = nodes[index].parent;
+                }
+                true
+            }
+            Self::ClosedSet(best_cost) => {
+                let (id, inserted) = arena.intern(state);
+                if inserted {
+                    best_cost.push(cost);
+                    return Some(Admitted::Stored(id));
+                }
+
+                let best = &mut best_cost[id.index()];
+                if !reopens || cost >= *best {
+                    return None;
+                }
+                *best = cost;
+                return Some(Admitted::Stored(id));
+            }
+            Self::Transposition(lru) => match lru.get(&state) {
+                Some(best) if !reopens || cost >= best => false,
+                _ => {
+                    lru.insert(state.clone(), cost);
+                    true
+                }
+            },
+            Self::Bloom(bloom) => bloom.insert(hash(&state)),
+            Self::HashCompaction(hashes, omissions) => {
+                // A new state collides with one of the stored hashes with
+                // probability n / 2^64.
+                #[allow(clippy::cast_precision_loss)]
+                let collision = hashes.len() as f64 / 2_f64.powi(64);
+                let inserted = hashes.insert(hash(&state));
+                if inserted {
+                    omissions.record(collision);
+                }
+                inserted
+            }
+        };
+
+        admitted.then(|| self.own(arena, state))
+    }
+
+    /// Returns the estimated probability that a reachable state has been
+    /// wrongly considered as already reached, if the strategy is
+    /// probabilistic.
+    pub fn omission_probability(&self) -> Option<f64> {
+        match self {
+            Self::Bloom(bloom) => Some(bloom.omissions.probability()),
+            Self::HashCompaction(_, omissions) => Some(omissions.probability()),
+            _ => None,
+        }
+    }
+}
+
+fn hash<T: Hash>(state: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Accumulates the probabilities of omitting each new state.
+#[derive(Default)]
+pub struct Omissions {
+    // Sum of ln(1 - p) over the insertions, ln_1p keeping precision for tiny
+    // probabilities.
+    log_no_omission: f64,
+}
+
+impl Omissions {
+    fn record(&mut self, probability: f64) {
+        self.log_no_omission += (-probability).ln_1p();
+    }
+
+    fn probability(&self) -> f64 {
+        -self.log_no_omission.exp_m1()
+    }
+}
+
+/// A Bloom filter over state hashes, a single hash function making it
+/// SPIN's bitstate hashing.
+pub struct BloomFilter {
+    words: Vec<u64>,
+    bits: u64,
+    hashes: u32,
+    ones: u64,
+    omissions: Omissions,
+}
+
+impl BloomFilter {
+    pub fn new(bits: usize, hashes: u32) -> Self {
+        assert!(bits > 0, "Bloom filter should not be empty");
+        assert!(hashes > 0, "Bloom filter should use at least one hash");
+
+        Self {
+            words: vec![0; bits.div_ceil(64)],
+            bits: bits as u64,
+            hashes,
+            ones: 0,
+            omissions: Omissions::default(),
+        }
+    }
+
+    /// Sets the bits of `hash`, returning whether one of them was unset.
+    pub fn insert(&mut self, hash: u64) -> bool {
+        #[allow(clippy::cast_precision_loss)]
+        let fill = self.ones as f64 / self.bits as f64;
+
+        // Double hashing deriving the indices from two halves of a mixed hash.
+        let h1 = hash;
+        let h2 = splitmix64(hash) | 1;
+
+        let mut inserted = false;
+        for i in 0..u64::from(self.hashes) {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.bits;
+            #[allow(clippy::cast_possible_truncation)]
+            let word = &mut self.words[(bit / 64) as usize];
+            let mask = 1 << (bit % 64);
+
+            if *word & mask == 0 {
+                *word |= mask;
+                self.ones += 1;
+                inserted = true;
+            }
+        }
+
+        // Each new state stands for the ones omitted because all their bits
+        // were already set.
+        if inserted {
+            self.omissions.record(fill.powf(f64::from(self.hashes)));
+        }
+
+        inserted
+    }
+}
+
+const fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A map of bounded size evicting the least recently used entries.
+pub struct Lru<T> {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<T, (f32, u64)>,
+    // Entries in order of use, including outdated uses which are skipped on
+    // eviction.
+    uses: VecDeque<(T, u64)>,
+}
+
+impl<T: Clone + Eq + Hash> Lru<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Transposition table should not be empty");
+
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::with_capacity(capacity),
+            uses: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn peek(&self, key: &T) -> Option<f32> {
+        self.entries.get(key).map(|&(value, _)| value)
+    }
+
+    pub fn get(&mut self, key: &T) -> Option<f32> {
+        let clock = self.clock;
+        let (value, last_use) = self.entries.get_mut(key)?;
+        *last_use = clock;
+        let value = *value;
+
+        self.touch(key.clone());
+        Some(value)
+    }
+
+    pub fn insert(&mut self, key: T, value: f32) {
+        if !self.entries.contains_key(&key) && self.entries.len() == self.capacity {
+            self.evict();
+        }
+
+        self.entries.insert(key.clone(), (value, self.clock));
+        self.touch(key);
+    }
+
+    fn touch(&mut self, key: T) {
+        self.uses.push_back((key, self.clock));
+        self.clock += 1;
+
+        if self.uses.len() > 2 * self.capacity {
+            let entries = &self.entries;
+            self.uses
+                .retain(|(key, last_use)| entries.get(key).map(|&(_, u)| u) == Some(*last_use));
+        }
+    }
+
+    fn evict(&mut self) {
+        while let Some((key, last_use)) = self.uses.pop_front() {
+            if self.entries.get(&key).map(|&(_, u)| u) == Some(last_use) {
+                self.entries.remove(&key);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bloom_filter_has_no_false_negative() {
+        let mut bloom = BloomFilter::new(1 << 12, 3);
+        for i in 0..100_u64 {
+            bloom.insert(hash(&i));
+        }
+
+        assert!((0..100_u64).all(|i| !bloom.insert(hash(&i))));
+    }
+
+    #[test]
+    fn omission_probability_grows_with_fill() {
+        let mut small = BloomFilter::new(256, 1);
+        let mut large = BloomFilter::new(1 << 16, 1);
+        for i in 0..100_u64 {
+            small.insert(hash(&i));
+            large.insert(hash(&i));
+        }
+
+        assert!(small.omissions.probability() > large.omissions.probability());
+        assert!(large.omissions.probability() > 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bitstate table should be addressable")]
+    fn bitstate_table_too_large() {
+        Visited::<u32>::new(DuplicateDetection::Bitstate { log2_bits: 64 });
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, 1.);
+        lru.insert(2, 2.);
+        lru.get(&1);
+        lru.insert(3, 3.);
+
+        assert_eq!(lru.peek(&1), Some(1.));
+        assert_eq!(lru.peek(&2), None);
+        assert_eq!(lru.peek(&3), Some(3.));
+    }
+
+    #[test]
+    fn lru_stays_bounded() {
+        let mut lru = Lru::new(3);
+        for i in 0..100 {
+            lru.insert(i % 7, 0.);
+            lru.get(&(i % 5));
+        }
+
+        assert!(lru.entries.len() <= 3);
+        assert!(lru.uses.len() <= 6);
+    }
+}
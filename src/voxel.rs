@@ -0,0 +1,459 @@
+//! Three-dimensional grids of voxels.
+//!
+//! A [`VoxelGrid`] is the volumetric counterpart of a
+//! [`Grid`](crate::grid::Grid), each voxel having a [`Terrain`]. Moves go
+//! through the faces, edges or corners of the voxels according to the
+//! [`Connectivity`], their lengths being 1, √2 and √3. Agents either fly
+//! freely or walk, standing on the voxels above impassable ones.
+//!
+//! ```
+//! # use state_space::grid::CellState::{Ground, Wall};
+//! # use state_space::voxel::{Connectivity, Movement, VoxelGrid};
+//! # use state_space::{HeuristicStateSpace, StateSpace};
+//! // A wall in the middle of the bottom layer, which fliers go over through
+//! // the layer above.
+//! let mut volume = VoxelGrid::filled(3, 1, 3, Ground);
+//! volume.set((1, 0, 0), Wall);
+//!
+//! assert_eq!(volume.a_star((0, 0, 0), (2, 0, 0)).len(), 5);
+//!
+//! let walker = volume.clone().with_movement(Movement::Walking);
+//! assert!(walker.search().run((0, 0, 0), (2, 0, 0)).path.is_none());
+//!
+//! let climber = walker.with_connectivity(Connectivity::Eighteen);
+//! assert_eq!(climber.a_star((0, 0, 0), (2, 0, 0)), vec![(0, 0, 0), (1, 0, 1), (2, 0, 0)]);
+//! ```
+use std::ops::Index;
+
+use crate::grid::Terrain;
+use crate::{CostStateSpace, HeuristicStateSpace, StateSpace};
+
+/// A position in a voxel grid, its last coordinate going up.
+pub type Voxel = (usize, usize, usize);
+
+/// The voxels adjacent to a voxel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// The voxels sharing a face.
+    Six,
+    /// The voxels sharing a face or an edge.
+    Eighteen,
+    /// The voxels sharing a face, an edge or a corner.
+    TwentySix,
+}
+
+/// How agents move in a [`VoxelGrid`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Movement {
+    /// Any passable voxel can be entered.
+    Flying,
+    /// Only the voxels supported by an impassable voxel below them, or on
+    /// the bottom layer, can be entered, which makes climbing a step require
+    /// moves through edges.
+    Walking,
+}
+
+/// The offsets of the adjacent voxels, through faces, then edges, then
+/// corners.
+const OFFSETS: [(isize, isize, isize); 26] = [
+    (0, 0, -1),
+    (0, -1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (0, -1, -1),
+    (-1, 0, -1),
+    (1, 0, -1),
+    (0, 1, -1),
+    (-1, -1, 0),
+    (1, -1, 0),
+    (-1, 1, 0),
+    (1, 1, 0),
+    (0, -1, 1),
+    (-1, 0, 1),
+    (1, 0, 1),
+    (0, 1, 1),
+    (-1, -1, -1),
+    (1, -1, -1),
+    (-1, 1, -1),
+    (1, 1, -1),
+    (-1, -1, 1),
+    (1, -1, 1),
+    (-1, 1, 1),
+    (1, 1, 1),
+];
+
+impl Connectivity {
+    /// Returns the offsets of the adjacent voxels.
+    #[must_use]
+    pub fn offsets(self) -> &'static [(isize, isize, isize)] {
+        match self {
+            Self::Six => &OFFSETS[..6],
+            Self::Eighteen => &OFFSETS[..18],
+            Self::TwentySix => &OFFSETS,
+        }
+    }
+
+    /// Returns the length of the shortest paths from `from` to `to` in an
+    /// empty volume.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn distance(self, from: Voxel, to: Voxel) -> f32 {
+        let mut deltas = [
+            from.0.abs_diff(to.0),
+            from.1.abs_diff(to.1),
+            from.2.abs_diff(to.2),
+        ];
+        deltas.sort_unstable();
+        let [low, mid, high] = deltas;
+
+        let (straight, edges, corners) = match self {
+            Self::Six => (low + mid + high, 0, 0),
+            // Each move through an edge covers two axes, which can all be
+            // paired unless one axis exceeds the others.
+            Self::Eighteen if high >= low + mid => (high - low - mid, low + mid, 0),
+            Self::Eighteen => ((low + mid + high) % 2, (low + mid + high) / 2, 0),
+            Self::TwentySix => (high - mid, mid - low, low),
+        };
+
+        (corners as f32).mul_add(
+            3_f32.sqrt(),
+            (edges as f32).mul_add(std::f32::consts::SQRT_2, straight as f32),
+        )
+    }
+}
+
+/// A grid of voxels of terrain `T`, whose states are the positions of the
+/// voxels which can be entered.
+///
+/// Entering a voxel costs its [`Terrain::cost`], times the length of the
+/// move. Moves through edges and corners cannot cut the corners of
+/// impassable voxels, apart from the steps climbed by walking agents, unless
+/// allowed with [`VoxelGrid::with_corner_cutting`].
+/// The heuristic is [`Connectivity::distance`] times the cheapest cost of
+/// the voxels, admissible whatever the movement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid<T> {
+    width: usize,
+    depth: usize,
+    height: usize,
+    voxels: Vec<T>,
+    connectivity: Connectivity,
+    movement: Movement,
+    corner_cutting: bool,
+    /// A lower bound on the cost of the passable voxels.
+    min_cost: f32,
+}
+
+impl<T: Terrain> VoxelGrid<T> {
+    /// Creates a 6-connected grid for flying agents of `width` voxels along
+    /// the first axis, `depth` along the second and `height` along the
+    /// vertical one from its voxels, the first coordinate varying fastest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `width * depth * height` voxels.
+    #[must_use]
+    pub fn new(width: usize, depth: usize, height: usize, voxels: Vec<T>) -> Self {
+        assert_eq!(
+            voxels.len(),
+            width * depth * height,
+            "A grid should have `width * depth * height` voxels"
+        );
+
+        let min_cost = voxels
+            .iter()
+            .filter_map(Terrain::cost)
+            .fold(f32::INFINITY, f32::min);
+
+        Self {
+            width,
+            depth,
+            height,
+            voxels,
+            connectivity: Connectivity::Six,
+            movement: Movement::Flying,
+            corner_cutting: false,
+            min_cost,
+        }
+    }
+
+    /// Creates a grid of the given dimensions filled with `voxel`.
+    #[must_use]
+    pub fn filled(width: usize, depth: usize, height: usize, voxel: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, depth, height, vec![voxel; width * depth * height])
+    }
+
+    /// Replaces the voxel at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of the grid.
+    pub fn set(&mut self, position: Voxel, voxel: T) {
+        assert!(
+            self.contains(position),
+            "Position {:?} should be in the grid",
+            position
+        );
+
+        if let Some(cost) = voxel.cost() {
+            self.min_cost = self.min_cost.min(cost);
+        }
+        let index = self.index_of(position);
+        self.voxels[index] = voxel;
+    }
+
+    /// Returns whether `position` is in the grid and is passable.
+    #[inline]
+    #[must_use]
+    pub fn is_passable(&self, position: Voxel) -> bool {
+        self.get(position).and_then(Terrain::cost).is_some()
+    }
+
+    /// Returns whether a walking agent can stand at `position`, on the
+    /// bottom layer or above an impassable voxel.
+    #[must_use]
+    pub fn is_supported(&self, (x, y, z): Voxel) -> bool {
+        z == 0 || !self.is_passable((x, y, z - 1))
+    }
+
+    /// Returns whether the agent can stand at `position` given its movement.
+    #[must_use]
+    pub fn can_enter(&self, position: Voxel) -> bool {
+        self.is_passable(position)
+            && (self.movement == Movement::Flying || self.is_supported(position))
+    }
+
+    /// Returns whether the voxels a move by `offset` from `position` goes
+    /// past, those reached by moving along some of its axes only, are
+    /// passable.
+    ///
+    /// Walking agents climbing or descending a step go over it, so only the
+    /// voxels on the upper level of the move matter.
+    fn clears_corners(&self, position: Voxel, (dx, dy, dz): (isize, isize, isize)) -> bool {
+        let moved = u8::from(dx != 0) | u8::from(dy != 0) << 1 | u8::from(dz != 0) << 2;
+        let along = |mask: u8, axis: u8, delta| if mask & axis == 0 { 0 } else { delta };
+        let upper = if dz > 0 { position.2 + 1 } else { position.2 };
+
+        (1..moved).filter(|mask| mask & !moved == 0).all(|mask| {
+            let part = (along(mask, 1, dx), along(mask, 2, dy), along(mask, 4, dz));
+            self.offset(position, part).is_some_and(|voxel| {
+                self.is_passable(voxel) || (self.movement == Movement::Walking && voxel.2 != upper)
+            })
+        })
+    }
+}
+
+impl<T> VoxelGrid<T> {
+    /// Sets the voxels adjacent to a voxel.
+    #[inline]
+    #[must_use]
+    pub const fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    /// Sets how agents move.
+    #[inline]
+    #[must_use]
+    pub const fn with_movement(mut self, movement: Movement) -> Self {
+        self.movement = movement;
+        self
+    }
+
+    /// Sets whether moves through edges and corners can go past impassable
+    /// voxels, which is forbidden by default.
+    #[inline]
+    #[must_use]
+    pub const fn with_corner_cutting(mut self, corner_cutting: bool) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    /// Returns the dimensions of the grid, as its width, depth and height.
+    #[inline]
+    #[must_use]
+    pub const fn dimensions(&self) -> (usize, usize, usize) {
+        (self.width, self.depth, self.height)
+    }
+
+    /// Returns whether `position` is in the grid.
+    #[inline]
+    #[must_use]
+    pub const fn contains(&self, (x, y, z): Voxel) -> bool {
+        x < self.width && y < self.depth && z < self.height
+    }
+
+    /// Returns the voxel at `position`, if it is in the grid.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, position: Voxel) -> Option<&T> {
+        if self.contains(position) {
+            self.voxels.get(self.index_of(position))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `position` moved by `offset`, if it is still in the grid.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self, (x, y, z): Voxel, (dx, dy, dz): (isize, isize, isize)) -> Option<Voxel> {
+        let position = (
+            x.checked_add_signed(dx)?,
+            y.checked_add_signed(dy)?,
+            z.checked_add_signed(dz)?,
+        );
+        Some(position).filter(|&position| self.contains(position))
+    }
+
+    const fn index_of(&self, (x, y, z): Voxel) -> usize {
+        x + self.width * (y + self.depth * z)
+    }
+}
+
+impl<T> Index<Voxel> for VoxelGrid<T> {
+    type Output = T;
+
+    /// Returns the voxel at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of the grid.
+    #[inline]
+    fn index(&self, position: Voxel) -> &T {
+        self.get(position).expect("Position should be in the grid")
+    }
+}
+
+impl<T: Terrain> StateSpace for VoxelGrid<T> {
+    type State = Voxel;
+
+    fn for_each_neighbour(&self, state: &Voxel, f: &mut dyn FnMut(Voxel)) {
+        for &offset in self.connectivity.offsets() {
+            let Some(next) = self.offset(*state, offset) else {
+                continue;
+            };
+
+            if self.can_enter(next) && (self.corner_cutting || self.clears_corners(*state, offset))
+            {
+                f(next);
+            }
+        }
+    }
+}
+
+impl<T: Terrain> CostStateSpace for VoxelGrid<T> {
+    /// Returns the cost of entering `next` times the length of the move,
+    /// infinite if it cannot be entered.
+    fn cost(&self, current: &Voxel, next: &Voxel) -> f32 {
+        let Some(cost) = self.get(*next).and_then(Terrain::cost) else {
+            return f32::INFINITY;
+        };
+
+        let axes = u8::from(current.0 != next.0)
+            + u8::from(current.1 != next.1)
+            + u8::from(current.2 != next.2);
+        match axes {
+            2 => cost * std::f32::consts::SQRT_2,
+            3 => cost * 3_f32.sqrt(),
+            _ => cost,
+        }
+    }
+}
+
+impl<T: Terrain> HeuristicStateSpace for VoxelGrid<T> {
+    #[inline]
+    fn heuristic(&self, state: &Voxel, goal: &Voxel) -> f32 {
+        if self.min_cost.is_finite() {
+            self.connectivity.distance(*state, *goal) * self.min_cost
+        } else {
+            0.
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::CellState::{Ground, Wall};
+
+    const CONNECTIVITIES: [Connectivity; 3] = [
+        Connectivity::Six,
+        Connectivity::Eighteen,
+        Connectivity::TwentySix,
+    ];
+
+    #[test]
+    fn neighbours() {
+        let cube = VoxelGrid::filled(3, 3, 3, Ground);
+
+        for (connectivity, count) in CONNECTIVITIES.iter().zip([6, 18, 26]) {
+            let cube = cube.clone().with_connectivity(*connectivity);
+            assert_eq!(cube.neighbours(&(1, 1, 1)).len(), count);
+        }
+        assert_eq!(
+            cube.neighbours(&(0, 0, 0)),
+            vec![(1, 0, 0), (0, 1, 0), (0, 0, 1)]
+        );
+        assert_eq!(cube.offset((0, 2, 0), (0, 1, 0)), None);
+    }
+
+    #[test]
+    fn corners_are_not_cut() {
+        // Two walls meeting along an edge.
+        let mut grid = VoxelGrid::filled(2, 2, 1, Ground).with_connectivity(Connectivity::Eighteen);
+        grid.set((1, 0, 0), Wall);
+
+        assert!(!grid.neighbours(&(0, 0, 0)).contains(&(1, 1, 0)));
+        assert!(grid
+            .clone()
+            .with_corner_cutting(true)
+            .neighbours(&(0, 0, 0))
+            .contains(&(1, 1, 0)));
+    }
+
+    #[test]
+    fn heuristics_are_exact_in_empty_volumes() {
+        let volume = VoxelGrid::filled(4, 3, 5, 3_u8);
+        let (from, to) = ((0, 0, 0), (3, 2, 4));
+
+        for connectivity in CONNECTIVITIES {
+            let volume = volume.clone().with_connectivity(connectivity);
+            let path = volume.a_star(from, to);
+            let cost: f32 = path.windows(2).map(|w| volume.cost(&w[0], &w[1])).sum();
+
+            assert!((volume.heuristic(&from, &to) - cost).abs() < 1e-4);
+            for state in &path {
+                let rest = volume.dijkstra(*state, to);
+                let rest: f32 = rest.windows(2).map(|w| volume.cost(&w[0], &w[1])).sum();
+                assert!(volume.heuristic(state, &to) <= rest + 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn walking_needs_support() {
+        // A floor with a step up to a ledge.
+        let mut volume = VoxelGrid::filled(4, 1, 3, Ground)
+            .with_movement(Movement::Walking)
+            .with_connectivity(Connectivity::Eighteen);
+        volume.set((2, 0, 0), Wall);
+        volume.set((3, 0, 0), Wall);
+
+        assert!(volume.is_supported((1, 0, 0)));
+        assert!(!volume.can_enter((1, 0, 1)));
+        assert_eq!(
+            volume.a_star((0, 0, 0), (3, 0, 1)),
+            vec![(0, 0, 0), (1, 0, 0), (2, 0, 1), (3, 0, 1)]
+        );
+
+        let flying = volume.clone().with_movement(Movement::Flying);
+        assert!(flying.neighbours(&(2, 0, 1)).contains(&(2, 0, 2)));
+        assert!(!volume.neighbours(&(2, 0, 1)).contains(&(2, 0, 2)));
+    }
+}